@@ -0,0 +1,37 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use advent_of_code::combinatorics::{
+    cartesian_product, combinations, heap_permutations, permutations, power_set,
+};
+
+/// lexicographic order against Heap's algorithm over the same items.
+fn orderings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("permutations");
+    for &length in [5_usize, 8].iter() {
+        let items: Vec<_> = (0..length).collect();
+        group.bench_with_input(BenchmarkId::new("lexicographic", length), &items, |b, i| {
+            b.iter(|| permutations(i).count())
+        });
+        group.bench_with_input(BenchmarkId::new("heap", length), &items, |b, i| {
+            b.iter(|| heap_permutations(i).count())
+        });
+    }
+    group.finish();
+}
+
+fn subsets(c: &mut Criterion) {
+    let items: Vec<_> = (0..16).collect();
+    c.bench_function("combinations 16 choose 8", |b| {
+        b.iter(|| combinations(&items, 8).count())
+    });
+    c.bench_function("power set of 16", |b| {
+        b.iter(|| power_set(&items).unwrap().count())
+    });
+    let sets = vec![(0..10).collect::<Vec<_>>(); 4];
+    c.bench_function("product of 4 sets of 10", |b| {
+        b.iter(|| cartesian_product(&sets).count())
+    });
+}
+
+criterion_group!(benches, orderings, subsets);
+criterion_main!(benches);
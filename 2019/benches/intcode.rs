@@ -0,0 +1,58 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use advent_of_code::{day09, day19};
+use intcode::Machine;
+
+mod baseline;
+
+fn read(day: &str) -> String {
+    std::fs::read_to_string(format!("./inputs/{}.txt", day)).unwrap()
+}
+fn load(day: &str) -> Machine {
+    Machine::from_string(&read(day)).unwrap()
+}
+
+/// the crate's machine against the interpreter it replaced, on the same
+/// puzzles.
+fn interpreters(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day09 part 2");
+    let machine = load("day09");
+    group.bench_function("intcode", |b| {
+        b.iter(|| day09::part2(&mut machine.clone()).unwrap())
+    });
+    let machine = baseline::Machine::from_string(&read("day09"));
+    group.bench_function("baseline", |b| {
+        b.iter(|| machine.clone().run(std::iter::once(2)))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("day19 part 1");
+    let machine = load("day19");
+    group.bench_function("intcode", |b| {
+        b.iter(|| day19::part1(&machine, 50).unwrap())
+    });
+    let machine = baseline::Machine::from_string(&read("day19"));
+    group.bench_function("baseline", |b| {
+        b.iter(|| {
+            let mut affected = 0;
+            for x in 0..50 {
+                for y in 0..50 {
+                    affected += machine.clone().run([x, y].iter().copied())[0];
+                }
+            }
+            affected
+        })
+    });
+    group.finish();
+}
+
+/// forking a machine that has been running for a while, the way searches
+/// over machine states do.
+fn clone(c: &mut Criterion) {
+    let mut machine = load("day25");
+    machine.run(std::iter::empty()).unwrap();
+    c.bench_function("clone day25", |b| b.iter(|| machine.clone()));
+}
+
+criterion_group!(benches, interpreters, clone);
+criterion_main!(benches);
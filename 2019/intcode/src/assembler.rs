@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use crate::disassembler::mnemonic;
+use crate::error::{Error, Result};
+use crate::machine::{Opcode, OpcodeType};
+
+const OPCODES: [i64; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+const POSITION: i64 = 0;
+const IMMEDIATE: i64 = 1;
+const RELATIVE: i64 = 2;
+
+enum Value<'a> {
+    Number(i64),
+    Label(&'a str, i64),
+}
+
+struct Operand<'a> {
+    mode: i64,
+    value: Value<'a>,
+}
+
+enum Statement<'a> {
+    Instruction(i64, Vec<Operand<'a>>),
+    Data(Vec<Value<'a>>),
+}
+
+struct Line<'a> {
+    number: usize,
+    statement: Statement<'a>,
+}
+
+fn error(line: usize, message: String) -> Error {
+    Error::parse(format!("line {}: {}", line, message))
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// a number, or a label with an optional offset like `loop+2`.
+fn value(text: &str) -> Option<Value<'_>> {
+    let text = text.trim();
+    if let Ok(number) = text.parse() {
+        return Some(Value::Number(number));
+    }
+    let split = text.find(['+', '-']).unwrap_or(text.len());
+    let (label, offset) = text.split_at(split);
+    let label = label.trim();
+    if !is_identifier(label) {
+        return None;
+    }
+    let offset = match offset.trim() {
+        "" => 0,
+        offset => offset.replace(' ', "").parse().ok()?,
+    };
+    Some(Value::Label(label, offset))
+}
+
+/// `[addr]`, `#value` or `rb+offset`.
+fn operand(text: &str) -> Option<Operand<'_>> {
+    let text = text.trim();
+    let (mode, value) = if let Some(addr) = text.strip_prefix('[') {
+        (POSITION, value(addr.strip_suffix(']')?)?)
+    } else if let Some(immediate) = text.strip_prefix('#') {
+        (IMMEDIATE, value(immediate)?)
+    } else if let Some(offset) = text.strip_prefix("rb") {
+        let offset = offset.trim();
+        if offset.is_empty() {
+            (RELATIVE, Value::Number(0))
+        } else if offset.starts_with(['+', '-']) {
+            let negative = offset.starts_with('-');
+            match value(&offset[1..])? {
+                Value::Number(number) if negative => (RELATIVE, Value::Number(-number)),
+                Value::Label(..) if negative => return None,
+                value => (RELATIVE, value),
+            }
+        } else {
+            return None;
+        }
+    } else {
+        return None;
+    };
+    Some(Operand { mode, value })
+}
+
+fn opcode(name: &str) -> Option<(i64, OpcodeType)> {
+    OPCODES.iter().find_map(|&code| {
+        let op = Opcode(code).op()?;
+        if mnemonic(op) == name {
+            Some((code, op))
+        } else {
+            None
+        }
+    })
+}
+
+fn instruction<'a>(line: usize, name: &str, args: &[&'a str]) -> Result<Statement<'a>> {
+    let (code, op) =
+        opcode(name).ok_or_else(|| error(line, format!("unknown mnemonic {:?}", name)))?;
+    if args.len() != op.num_params() {
+        let message = format!(
+            "{} takes {} operands, found {}",
+            name,
+            op.num_params(),
+            args.len()
+        );
+        return Err(error(line, message));
+    }
+    let operands = args
+        .iter()
+        .map(|arg| operand(arg).ok_or_else(|| error(line, format!("invalid operand {:?}", arg))))
+        .collect::<Result<Vec<_>>>()?;
+    if op.writes() && operands.last().map(|operand| operand.mode) == Some(IMMEDIATE) {
+        return Err(error(line, format!("{} can't write to an immediate", name)));
+    }
+    Ok(Statement::Instruction(code, operands))
+}
+
+/// Assemble a program into Intcode. Each line holds an instruction like
+/// `add [10], #3, rb+1` or a `data` directive with comma separated values,
+/// optionally preceded by labels. A numeric label such as `12:` checks that
+/// the line starts at that address, so disassembler listings assemble back
+/// into the same program. Comments start with `;`.
+pub fn assemble(source: &str) -> Result<Vec<i64>> {
+    let mut labels = HashMap::new();
+    let mut lines = Vec::new();
+    let mut address = 0;
+
+    for (index, text) in source.lines().enumerate() {
+        let number = index + 1;
+        let mut text = text.split(';').next().unwrap_or("").trim();
+
+        while let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if let Ok(expected) = label.parse::<usize>() {
+                if expected != address {
+                    let message = format!("expected address {}, found {}", expected, address);
+                    return Err(error(number, message));
+                }
+            } else if !is_identifier(label) {
+                return Err(error(number, format!("invalid label {:?}", label)));
+            } else if labels.insert(label, address).is_some() {
+                return Err(error(number, format!("duplicate label {:?}", label)));
+            }
+            text = rest.trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        let (name, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let args: Vec<&str> = if args.trim().is_empty() {
+            Vec::new()
+        } else {
+            args.split(',').collect()
+        };
+        let statement = if name == "data" {
+            let values = args
+                .iter()
+                .map(|arg| {
+                    value(arg).ok_or_else(|| error(number, format!("invalid value {:?}", arg)))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            address += values.len();
+            Statement::Data(values)
+        } else {
+            let statement = instruction(number, name, &args)?;
+            address += args.len() + 1;
+            statement
+        };
+        lines.push(Line { number, statement });
+    }
+
+    let resolve = |line: usize, value: &Value<'_>| match value {
+        Value::Number(number) => Ok(*number),
+        Value::Label(label, offset) => labels
+            .get(label)
+            .map(|&address| address as i64 + offset)
+            .ok_or_else(|| error(line, format!("undefined label {:?}", label))),
+    };
+
+    let mut program = Vec::with_capacity(address);
+    for line in lines.iter() {
+        match &line.statement {
+            Statement::Instruction(code, operands) => {
+                let modes = operands
+                    .iter()
+                    .rev()
+                    .fold(0, |modes, operand| modes * 10 + operand.mode);
+                program.push(modes * 100 + code);
+                for operand in operands.iter() {
+                    program.push(resolve(line.number, &operand.value)?);
+                }
+            }
+            Statement::Data(values) => {
+                for value in values.iter() {
+                    program.push(resolve(line.number, value)?);
+                }
+            }
+        }
+    }
+    Ok(program)
+}
+
+/// format a program the way puzzle inputs are written.
+pub fn to_string(program: &[i64]) -> String {
+    let numbers: Vec<_> = program.iter().map(i64::to_string).collect();
+    numbers.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassembler::disassemble;
+    use crate::machine::Machine;
+
+    #[test]
+    fn labels_and_modes() {
+        let source = "
+            ; print the numbers 3, 2, 1
+            loop: out [count]
+                  add [count], #-1, [count]
+                  jnz [count], #loop
+                  hlt
+            count: data 3
+        ";
+        let program = assemble(source).unwrap();
+        assert_eq!(program, vec![4, 10, 1001, 10, -1, 10, 1005, 10, 0, 99, 3]);
+
+        let mut machine = Machine::from_string(&to_string(&program)).unwrap();
+        assert_eq!(machine.run(std::iter::empty()).unwrap(), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn relative_operands() {
+        let program = assemble("arb #10\nadd rb, rb-1, rb+2\nhlt").unwrap();
+        assert_eq!(program, vec![109, 10, 22201, 0, -1, 2, 99]);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(assemble("nop").is_err());
+        assert!(assemble("add #1, #2").is_err());
+        assert!(assemble("add #1, #2, #3").is_err());
+        assert!(assemble("jz #0, #nowhere").is_err());
+        assert!(assemble("1: hlt").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let inputs = [
+            include_str!("../tests/data/day09.txt"),
+            include_str!("../tests/data/day25.txt"),
+        ];
+        for input in inputs.iter() {
+            let machine = Machine::from_string(input).unwrap();
+            let listing = disassemble(&machine.memory()).to_string();
+            assert_eq!(assemble(&listing).unwrap(), machine.memory());
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::machine::{Opcode, OpcodeType, ParameterMode};
+
+/// data values printed per line of a listing.
+const DATA_PER_LINE: usize = 8;
+
+pub fn mnemonic(op: OpcodeType) -> &'static str {
+    match op {
+        OpcodeType::Add => "add",
+        OpcodeType::Mul => "mul",
+        OpcodeType::Read => "in",
+        OpcodeType::Write => "out",
+        OpcodeType::JumpNotZero => "jnz",
+        OpcodeType::JumpZero => "jz",
+        OpcodeType::LessThan => "lt",
+        OpcodeType::Equals => "eq",
+        OpcodeType::StackPtrAdd => "arb",
+        OpcodeType::Halt => "hlt",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Position(i64),
+    Immediate(i64),
+    Relative(i64),
+}
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Position(addr) => write!(f, "[{}]", addr),
+            Operand::Immediate(value) => write!(f, "#{}", value),
+            Operand::Relative(offset) if *offset < 0 => write!(f, "rb{}", offset),
+            Operand::Relative(offset) => write!(f, "rb+{}", offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub address: usize,
+    pub op: OpcodeType,
+    pub operands: Vec<Operand>,
+}
+impl Instruction {
+    /// the number of memory cells the instruction takes up.
+    pub fn size(&self) -> usize {
+        self.operands.len() + 1
+    }
+    fn immediate(&self, index: usize) -> Option<i64> {
+        match self.operands[index] {
+            Operand::Immediate(value) => Some(value),
+            _ => None,
+        }
+    }
+    /// addresses that execution can continue at, as far as we can tell
+    /// without running the program.
+    fn successors(&self) -> Vec<usize> {
+        let next = self.address + self.size();
+        let taken = match self.op {
+            OpcodeType::Halt => return Vec::new(),
+            OpcodeType::JumpNotZero => self.immediate(0).map(|predicate| predicate != 0),
+            OpcodeType::JumpZero => self.immediate(0).map(|predicate| predicate == 0),
+            _ => return vec![next],
+        };
+        let target = self
+            .immediate(1)
+            .and_then(|target| usize::try_from(target).ok());
+
+        match taken {
+            Some(true) => target.into_iter().collect(),
+            Some(false) => vec![next],
+            None => std::iter::once(next).chain(target).collect(),
+        }
+    }
+    /// a value computed only from immediates and pushed onto the stack. One
+    /// that overflows isn't a constant, the machine would fault on it.
+    fn pushed_constant(&self) -> Option<usize> {
+        if !matches!(self.operands.last(), Some(Operand::Relative(_))) {
+            return None;
+        }
+        let value = match self.op {
+            OpcodeType::Add => self.immediate(0)?.checked_add(self.immediate(1)?)?,
+            OpcodeType::Mul => self.immediate(0)?.checked_mul(self.immediate(1)?)?,
+            _ => return None,
+        };
+        usize::try_from(value).ok()
+    }
+    fn is_jump(&self) -> bool {
+        matches!(self.op, OpcodeType::JumpNotZero | OpcodeType::JumpZero)
+    }
+}
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", mnemonic(self.op))?;
+        for (i, operand) in self.operands.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(f, "{}{}", separator, operand)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decode the instruction at `address`. Anything the VM would fault on, or
+/// that has mode digits the instruction doesn't use, isn't an instruction.
+pub fn decode(mem: &[i64], address: usize) -> Option<Instruction> {
+    decode_with(|addr| mem.get(addr).copied(), address)
+}
+
+/// `decode`, reading memory through `read`.
+pub fn decode_with<F>(read: F, address: usize) -> Option<Instruction>
+where
+    F: Fn(usize) -> Option<i64>,
+{
+    let opcode = Opcode(read(address)?);
+    let op = opcode.op()?;
+    let mut modes = opcode.param_modes();
+
+    let mut operands = Vec::new();
+    for i in 0..op.num_params() {
+        let value = read(address + 1 + i)?;
+        let operand = match modes.next().ok()? {
+            ParameterMode::Position => Operand::Position(value),
+            ParameterMode::Immediate => Operand::Immediate(value),
+            ParameterMode::Relative => Operand::Relative(value),
+        };
+        operands.push(operand);
+    }
+    if opcode.0 / 100 / 10_i64.pow(op.num_params() as u32) != 0 {
+        return None;
+    }
+    if op.writes() && matches!(operands.last(), Some(Operand::Immediate(_))) {
+        return None;
+    }
+    Some(Instruction {
+        address,
+        op,
+        operands,
+    })
+}
+
+/// Find the instructions reachable from address 0. Jumps through memory
+/// can't be followed, but a call pushes the address just after its jump onto
+/// the stack right before jumping, so that address is followed too.
+pub fn scan(mem: &[i64]) -> BTreeMap<usize, Instruction> {
+    let mut code = BTreeMap::new();
+    let mut pending = vec![0];
+
+    loop {
+        while let Some(address) = pending.pop() {
+            if code.contains_key(&address) {
+                continue;
+            }
+            if let Some(instruction) = decode(mem, address) {
+                pending.extend(instruction.successors());
+                code.insert(address, instruction);
+            }
+        }
+
+        for push in code.values() {
+            let jump = match code.get(&(push.address + push.size())) {
+                Some(jump) if jump.is_jump() => jump,
+                _ => continue,
+            };
+            let return_address = jump.address + jump.size();
+            if push.pushed_constant() == Some(return_address) && !code.contains_key(&return_address)
+            {
+                pending.push(return_address);
+            }
+        }
+        if pending.is_empty() {
+            return code;
+        }
+    }
+}
+
+pub enum Item {
+    Code(Instruction),
+    /// code that starts inside the operands of the instruction before it.
+    Overlap(Instruction),
+    Data(Vec<i64>),
+}
+
+/// A program split up into code and data.
+pub struct Listing {
+    items: Vec<(usize, Item)>,
+}
+impl Listing {
+    pub fn items(&self) -> &[(usize, Item)] {
+        &self.items
+    }
+}
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (address, item) in self.items.iter() {
+            match item {
+                Item::Code(instruction) => writeln!(f, "{:5}: {}", address, instruction)?,
+                // commented out, so that the listing still assembles.
+                Item::Overlap(instruction) => writeln!(
+                    f,
+                    "      ; {}: {}, overlapping the instruction before",
+                    address, instruction
+                )?,
+                Item::Data(values) => {
+                    let values: Vec<_> = values.iter().map(i64::to_string).collect();
+                    writeln!(f, "{:5}: data {}", address, values.join(", "))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn disassemble(mem: &[i64]) -> Listing {
+    let code = scan(mem);
+    let mut items = Vec::new();
+    let mut address = 0;
+
+    while address < mem.len() {
+        if let Some(instruction) = code.get(&address) {
+            address += instruction.size();
+            items.push((instruction.address, Item::Code(instruction.clone())));
+            for (&start, overlap) in code.range(instruction.address + 1..address) {
+                items.push((start, Item::Overlap(overlap.clone())));
+            }
+            continue;
+        }
+        let start = address;
+        while address < mem.len() && address - start < DATA_PER_LINE && !code.contains_key(&address)
+        {
+            address += 1;
+        }
+        items.push((start, Item::Data(mem[start..address].to_vec())));
+    }
+    Listing { items }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::Machine;
+
+    #[test]
+    fn modes() {
+        let machine = Machine::from_string("1002,4,3,4,33").unwrap();
+        let listing = disassemble(&machine.memory()).to_string();
+        assert_eq!(listing, "    0: mul [4], #3, [4]\n    4: data 33\n");
+    }
+
+    #[test]
+    fn relative_operands() {
+        let machine = Machine::from_string("109,-3,22201,1,-2,0,99").unwrap();
+        let listing = disassemble(&machine.memory()).to_string();
+        assert_eq!(
+            listing,
+            "    0: arb #-3\n    2: add rb+1, rb-2, rb+0\n    6: hlt\n"
+        );
+    }
+
+    #[test]
+    fn call_and_return() {
+        // push a return address, call a function that jumps back through it.
+        let machine = Machine::from_string("21101,7,0,100,1106,0,9,99,5,2106,0,100").unwrap();
+        let listing = disassemble(&machine.memory()).to_string();
+        assert_eq!(
+            listing,
+            "    0: add #7, #0, rb+100\n    4: jz #0, #9\n    7: hlt\n    8: data 5\n\
+             \x20   9: jz #0, rb+100\n"
+        );
+    }
+
+    #[test]
+    fn overlapping_code() {
+        // maybe jump into the middle of an add, whose second operand reads as
+        // a halt.
+        let machine = Machine::from_string("1005,10,5,1101,0,99,10,99").unwrap();
+        let listing = disassemble(&machine.memory()).to_string();
+        assert_eq!(
+            listing,
+            "    0: jnz [10], #5\n    3: add #0, #99, [10]\n\
+             \x20     ; 5: hlt, overlapping the instruction before\n    7: hlt\n"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+use std::io;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A program or assembly listing was malformed.
+    Parse(String),
+    /// A program did something illegal. `pc` is the address of the faulting
+    /// instruction.
+    Machine {
+        pc: usize,
+        opcode: i64,
+        fault: Fault,
+    },
+    /// Reading or writing the terminal, or writing a trace, failed.
+    Io(io::Error),
+}
+impl Error {
+    pub fn parse<S: Into<String>>(message: S) -> Self {
+        Error::Parse(message.into())
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(message) => write!(f, "parse error: {}", message),
+            Error::Machine { pc, opcode, fault } => {
+                write!(f, "{} at pc {} (opcode {})", fault, pc, opcode)
+            }
+            Error::Io(err) => write!(f, "io error: {}", err),
+        }
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Parse(_) | Error::Machine { .. } => None,
+        }
+    }
+}
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// What was illegal about the faulting instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    InvalidOpcode,
+    InvalidParameterMode(i64),
+    WriteToImmediate,
+    NegativeAddress(i64),
+    /// An add, multiply or relative base adjustment overflowed an `i64`.
+    Overflow,
+}
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fault::InvalidOpcode => write!(f, "invalid opcode"),
+            Fault::InvalidParameterMode(mode) => write!(f, "invalid parameter mode {}", mode),
+            Fault::WriteToImmediate => write!(f, "write to an immediate parameter"),
+            Fault::NegativeAddress(addr) => write!(f, "negative address {}", addr),
+            Fault::Overflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::VecDeque;
+
+use crate::memory::Memory;
+
+/// A machine's registers and memory at some point, from
+/// `Machine::snapshot`, for `Machine::restore` to go back to.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub(crate) pc: usize,
+    pub(crate) sp: i64,
+    pub(crate) mem: Memory,
+}
+
+/// A ring buffer of snapshots, one per input read, dropping the oldest once
+/// it's full. Snapshots share memory pages with the machine, so each one
+/// only costs the pages written before the next input.
+#[derive(Debug, Clone)]
+pub struct History {
+    capacity: usize,
+    snapshots: VecDeque<Snapshot>,
+}
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            capacity,
+            snapshots: VecDeque::with_capacity(capacity.min(1024)),
+        }
+    }
+    /// how many inputs can be undone.
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub(crate) fn push(&mut self, snapshot: Snapshot) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+    /// the snapshot from before the last `inputs` inputs, forgetting the
+    /// ones after it, and how many inputs that goes back.
+    pub(crate) fn rewind(&mut self, inputs: usize) -> Option<(Snapshot, usize)> {
+        let inputs = inputs.min(self.snapshots.len());
+        if inputs == 0 {
+            return None;
+        }
+        let at = self.snapshots.len() - inputs;
+        let snapshot = self.snapshots.drain(at..).next()?;
+        Some((snapshot, inputs))
+    }
+}
@@ -0,0 +1,40 @@
+//! The Intcode computer from Advent of Code 2019, with an assembler,
+//! disassembler and tracer for working on its programs.
+//!
+//! - Loading: `Machine::from_string` takes a puzzle input,
+//!   `Machine::new` the cells of a program.
+//! - Running: `Machine::run` runs until the machine halts or runs out of
+//!   input, `resume` stops at each output, `run_until` stops after a number
+//!   of outputs or instructions, and `step` executes one instruction.
+//!   Input is any iterator of `i64`, and a machine that runs out waits at
+//!   the input instruction until it's run again with more.
+//! - Memory: `mem_get` and `mem_set` read and write any address, memory
+//!   growing as it's written.
+//! - Snapshots: `Machine::snapshot` and `Machine::restore` save and go back
+//!   to a machine's state, and `set_history` keeps one from before every
+//!   input so that `rewind` can undo them.
+//!
+//! ```
+//! use intcode::{Machine, StopReason};
+//!
+//! // add one to each input.
+//! let mut machine = Machine::from_string("3,11,101,1,11,11,4,11,1105,1,0,0").unwrap();
+//! assert_eq!(machine.run(vec![1, 2].into_iter()).unwrap(), vec![2, 3]);
+//! assert_eq!(machine.mem_get(11), 3);
+//! assert_eq!(
+//!     machine.resume(std::iter::empty()).unwrap(),
+//!     StopReason::NeedsInput
+//! );
+//! ```
+
+pub mod assembler;
+pub mod disassembler;
+mod error;
+mod history;
+mod machine;
+mod memory;
+pub mod tracer;
+
+pub use error::{Error, Fault, Result};
+pub use history::Snapshot;
+pub use machine::{Limit, Machine, OpcodeType, StopReason};
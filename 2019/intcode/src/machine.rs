@@ -0,0 +1,725 @@
+use std::convert::TryFrom;
+use std::io::{BufRead, Write};
+
+use nom::character::complete::{char, digit1};
+use nom::combinator::{map, map_res, opt, recognize};
+use nom::multi::separated_list;
+use nom::sequence::tuple;
+use nom::IResult;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Fault, Result};
+use crate::history::{History, Snapshot};
+use crate::memory::Memory;
+use crate::tracer::{Entry, SharedTracer};
+
+fn number_p(input: &str) -> IResult<&str, i64> {
+    map_res(recognize(tuple((opt(char('-')), digit1))), str::parse)(input)
+}
+
+fn program_p(input: &str) -> IResult<&str, Machine> {
+    map(separated_list(char(','), number_p), Machine::new)(input)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum ParameterMode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+pub(crate) struct ParameterModes(i64);
+impl ParameterModes {
+    pub(crate) fn next(&mut self) -> std::result::Result<ParameterMode, i64> {
+        let result = match self.0 % 10 {
+            0 => ParameterMode::Position,
+            1 => ParameterMode::Immediate,
+            2 => ParameterMode::Relative,
+            mode => return Err(mode),
+        };
+        self.0 /= 10;
+        Ok(result)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OpcodeType {
+    Add,
+    Mul,
+    Read,
+    Write,
+    JumpNotZero,
+    JumpZero,
+    LessThan,
+    Equals,
+    Halt,
+    StackPtrAdd,
+}
+
+impl OpcodeType {
+    /// the number of parameters following the opcode.
+    #[inline]
+    pub fn num_params(self) -> usize {
+        match self {
+            OpcodeType::Add | OpcodeType::Mul | OpcodeType::LessThan | OpcodeType::Equals => 3,
+            OpcodeType::JumpNotZero | OpcodeType::JumpZero => 2,
+            OpcodeType::Read | OpcodeType::Write | OpcodeType::StackPtrAdd => 1,
+            OpcodeType::Halt => 0,
+        }
+    }
+    /// whether the last parameter is written to.
+    #[inline]
+    pub fn writes(self) -> bool {
+        matches!(
+            self,
+            OpcodeType::Add
+                | OpcodeType::Mul
+                | OpcodeType::Read
+                | OpcodeType::LessThan
+                | OpcodeType::Equals
+        )
+    }
+}
+
+pub(crate) struct Opcode(pub(crate) i64);
+impl Opcode {
+    pub(crate) fn op(&self) -> Option<OpcodeType> {
+        let op = match self.0 % 100 {
+            1 => OpcodeType::Add,
+            2 => OpcodeType::Mul,
+            3 => OpcodeType::Read,
+            4 => OpcodeType::Write,
+            5 => OpcodeType::JumpNotZero,
+            6 => OpcodeType::JumpZero,
+            7 => OpcodeType::LessThan,
+            8 => OpcodeType::Equals,
+            9 => OpcodeType::StackPtrAdd,
+            99 => OpcodeType::Halt,
+            _ => return None,
+        };
+        Some(op)
+    }
+    pub(crate) fn param_modes(&self) -> ParameterModes {
+        ParameterModes(self.0 / 100)
+    }
+}
+
+/// An opcode decoded into its operation and parameter modes. Machines cache
+/// these for every cell of memory, so that an instruction only has to be
+/// decoded again after something writes over it.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Decoded {
+    op: OpcodeType,
+    modes: [ParameterMode; 3],
+}
+impl Decoded {
+    pub(crate) fn new(opcode: i64) -> std::result::Result<Decoded, Fault> {
+        let op = Opcode(opcode).op().ok_or(Fault::InvalidOpcode)?;
+        let mut param_modes = Opcode(opcode).param_modes();
+        let mut modes = [ParameterMode::Position; 3];
+        for mode in modes.iter_mut().take(op.num_params()) {
+            *mode = param_modes.next().map_err(Fault::InvalidParameterMode)?;
+        }
+        if op.writes() && modes[op.num_params() - 1] == ParameterMode::Immediate {
+            return Err(Fault::WriteToImmediate);
+        }
+        Ok(Decoded { op, modes })
+    }
+}
+
+/// Why a machine stopped running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Halted,
+    /// The machine is waiting on an input instruction, resuming with more
+    /// input continues from there.
+    NeedsInput,
+    Output(i64),
+}
+
+/// How far `Machine::run_until` should run before pausing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Outputs(usize),
+    Instructions(usize),
+}
+
+/// inputs a terminal session can be rewound by.
+const TERMINAL_HISTORY: usize = 4096;
+
+/// An Intcode computer: its memory, program counter and relative base.
+///
+/// Machines are cheap to clone, since clones share memory until one of them
+/// writes to it, so searches can fork a machine at every choice. A machine
+/// serializes to its registers and memory, without its tracer or history.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Machine {
+    pc: usize,
+    sp: i64,
+    mem: Memory,
+    #[serde(skip)]
+    tracer: Option<SharedTracer>,
+    #[serde(skip)]
+    history: Option<History>,
+}
+impl Machine {
+    /// Load a comma separated program, like a puzzle input.
+    pub fn from_string(string: &str) -> Result<Machine> {
+        let (rest, machine) = program_p(string.trim())
+            .map_err(|_| Error::parse("expected a comma separated Intcode program"))?;
+        if !rest.is_empty() {
+            let line: String = rest.chars().take(20).collect();
+            return Err(Error::parse(format!(
+                "unexpected {:?} in Intcode program",
+                line
+            )));
+        }
+        Ok(machine)
+    }
+    /// Load a program, starting at address 0 with a relative base of 0.
+    pub fn new(mem: Vec<i64>) -> Machine {
+        Machine {
+            pc: 0,
+            sp: 0,
+            mem: Memory::new(mem),
+            tracer: None,
+            history: None,
+        }
+    }
+    /// The machine with `tracer` set, see `set_tracer`.
+    pub fn with_tracer(mut self, tracer: SharedTracer) -> Machine {
+        self.set_tracer(Some(tracer));
+        self
+    }
+    /// trace every instruction this machine, and any clones made from now
+    /// on, execute.
+    pub fn set_tracer(&mut self, tracer: Option<SharedTracer>) {
+        self.tracer = tracer;
+    }
+    /// Keep a snapshot from before each of the last `capacity` inputs read,
+    /// so that the machine can be rewound. 0 turns the history off.
+    pub fn set_history(&mut self, capacity: usize) {
+        self.history = match capacity {
+            0 => None,
+            capacity => Some(History::new(capacity)),
+        };
+    }
+    /// how many inputs the machine can be rewound by.
+    pub fn history_len(&self) -> usize {
+        self.history.as_ref().map_or(0, History::len)
+    }
+    /// Go back to just before the last `inputs` inputs were read, or as far
+    /// as the history goes. The machine will ask for the first of them
+    /// again. Returns how many inputs were undone.
+    pub fn rewind(&mut self, inputs: usize) -> usize {
+        let rewound = self
+            .history
+            .as_mut()
+            .and_then(|history| history.rewind(inputs));
+        match rewound {
+            Some((snapshot, inputs)) => {
+                self.restore(&snapshot);
+                inputs
+            }
+            None => 0,
+        }
+    }
+    #[cold]
+    fn record_snapshot(&mut self) {
+        let snapshot = self.snapshot();
+        if let Some(history) = self.history.as_mut() {
+            history.push(snapshot);
+        }
+    }
+
+    /// The machine's registers and memory as they are now. This shares
+    /// memory with the machine, copying a page only once one side writes to
+    /// it again, so taking a snapshot is cheap however big memory is.
+    pub fn snapshot(&mut self) -> Snapshot {
+        Snapshot {
+            pc: self.pc,
+            sp: self.sp,
+            mem: self.mem.snapshot(),
+        }
+    }
+    /// Put the machine back the way it was when `snapshot` was taken. The
+    /// history and tracer are kept.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.pc = snapshot.pc;
+        self.sp = snapshot.sp;
+        self.mem = snapshot.mem.clone();
+    }
+
+    /// whether the next instruction is a halt.
+    pub fn is_halted(&self) -> bool {
+        if let Some(OpcodeType::Halt) = Opcode(self.mem_get(self.pc)).op() {
+            return true;
+        }
+        false
+    }
+    /// one past the highest address loaded or written.
+    pub fn mem_size(&self) -> usize {
+        self.mem.len()
+    }
+    /// a copy of memory, up to the highest address used.
+    pub fn memory(&self) -> Vec<i64> {
+        self.mem.to_vec()
+    }
+    /// the address of the next instruction.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+    /// the relative base.
+    pub fn sp(&self) -> i64 {
+        self.sp
+    }
+
+    /// Set the program counter and relative base, for restoring a machine
+    /// saved some other way than a snapshot.
+    pub fn set_registers(&mut self, pc: usize, sp: i64) {
+        self.pc = pc;
+        self.sp = sp;
+    }
+
+    /// run the intcode VM as an interactive terminal, reading lines from
+    /// `input` and writing what the machine prints to `output`, and returning
+    /// everything it printed. `!back [n]` undoes the last n lines typed.
+    pub fn terminal<R, W>(&mut self, mut input: R, mut output: W) -> Result<Vec<i64>>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        let mut buffer = String::new();
+        let mut transcript = Vec::new();
+        // how many characters of each line the machine read.
+        let mut lines = Vec::new();
+        if self.history.is_none() {
+            self.set_history(TERMINAL_HISTORY);
+        }
+
+        loop {
+            if let Some(count) = buffer.trim().strip_prefix("!back") {
+                let count = count.trim().parse().unwrap_or(1).min(lines.len());
+                let inputs: usize = lines[lines.len() - count..].iter().sum();
+                if inputs > self.history_len() {
+                    writeln!(output, "can't go back that far")?;
+                } else {
+                    self.rewind(inputs);
+                    lines.truncate(lines.len() - count);
+                    let plural = if count == 1 { "" } else { "s" };
+                    writeln!(output, "went back {} line{}", count, plural)?;
+                }
+                buffer.clear();
+            }
+
+            let mut read = 0;
+            let line = buffer
+                .chars()
+                .map(|c| i64::from(u32::from(c)))
+                .inspect(|_| read += 1);
+            let printed = self.run(line)?;
+            if !buffer.is_empty() {
+                lines.push(read);
+            }
+            for char_num in printed.into_iter() {
+                match u8::try_from(char_num) {
+                    Ok(char_8) => write!(output, "{}", char::from(char_8))?,
+                    Err(_) => write!(output, "{}", char_num)?,
+                }
+                transcript.push(char_num);
+            }
+            if self.is_halted() {
+                writeln!(output)?;
+                return Ok(transcript);
+            }
+            output.flush()?;
+            buffer.clear();
+            if input.read_line(&mut buffer)? == 0 {
+                // stdin is closed, the machine will never get more input.
+                return Ok(transcript);
+            }
+        }
+    }
+    /// write memory to `output` as text, with anything that isn't a byte in
+    /// brackets.
+    pub fn print_mem<W: Write>(&self, mut output: W) -> Result<()> {
+        for char_num in self.mem.to_vec() {
+            match u8::try_from(char_num) {
+                Ok(char_8) => write!(output, "{}", char::from(char_8))?,
+                Err(_) => write!(output, "({})", char_num)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Run until the machine halts or needs input that `input` can't
+    /// provide. On a fault the program counter is left at the faulting
+    /// instruction.
+    pub fn run<I>(&mut self, mut input: I) -> Result<Vec<i64>>
+    where
+        I: Iterator<Item = i64>,
+    {
+        let mut output = Vec::new();
+        loop {
+            match self.resume(&mut input)? {
+                StopReason::Output(value) => output.push(value),
+                StopReason::Halted | StopReason::NeedsInput => return Ok(output),
+            }
+        }
+    }
+
+    /// Run until the next output, or until the machine halts or needs input
+    /// that `input` can't provide.
+    pub fn resume<I>(&mut self, mut input: I) -> Result<StopReason>
+    where
+        I: Iterator<Item = i64>,
+    {
+        if self.tracer.is_some() {
+            loop {
+                if let Some(reason) = self.traced_step(&mut input)? {
+                    return Ok(reason);
+                }
+            }
+        }
+        loop {
+            if let Some(reason) = self.execute(&mut input)? {
+                return Ok(reason);
+            }
+        }
+    }
+
+    /// Run until `limit` is reached, returning the outputs produced. The stop
+    /// reason is `None` if the limit was reached, otherwise the machine halted
+    /// or needs input.
+    pub fn run_until<I>(
+        &mut self,
+        mut input: I,
+        limit: Limit,
+    ) -> Result<(Vec<i64>, Option<StopReason>)>
+    where
+        I: Iterator<Item = i64>,
+    {
+        let mut output = Vec::new();
+        let mut instructions = 0;
+        loop {
+            let done = match limit {
+                Limit::Outputs(count) => output.len() >= count,
+                Limit::Instructions(count) => instructions >= count,
+            };
+            if done {
+                return Ok((output, None));
+            }
+            match self.step(&mut input)? {
+                None => {}
+                Some(StopReason::Output(value)) => output.push(value),
+                Some(reason) => return Ok((output, Some(reason))),
+            }
+            instructions += 1;
+        }
+    }
+
+    /// Execute a single instruction. Returns `None` if the instruction
+    /// produced nothing of interest. A machine that is halted or needs input
+    /// doesn't move. On a fault the program counter is left at the faulting
+    /// instruction.
+    pub fn step<I>(&mut self, mut input: I) -> Result<Option<StopReason>>
+    where
+        I: Iterator<Item = i64>,
+    {
+        if self.tracer.is_some() {
+            self.traced_step(&mut input)
+        } else {
+            self.execute(&mut input)
+        }
+    }
+
+    /// `step`, recording the instruction with the tracer if it executes.
+    #[cold]
+    fn traced_step<I>(&mut self, input: &mut I) -> Result<Option<StopReason>>
+    where
+        I: Iterator<Item = i64>,
+    {
+        let entry = Entry::new(self);
+        let result = self.execute(input);
+        if let (Some(tracer), Some(entry), Ok(None) | Ok(Some(StopReason::Output(_)))) =
+            (&self.tracer, entry, &result)
+        {
+            tracer.lock().unwrap().record(entry, self);
+        }
+        result
+    }
+
+    /// Execute the instruction at the program counter. Nothing changes
+    /// unless it executes, so a machine that faults, halts or needs input is
+    /// left at the same instruction.
+    ///
+    /// This and the parameter helpers are always inlined: machines are run
+    /// from other crates, where a call per instruction or parameter costs
+    /// more than the instruction itself.
+    #[inline(always)]
+    fn execute<I>(&mut self, input: &mut I) -> Result<Option<StopReason>>
+    where
+        I: Iterator<Item = i64>,
+    {
+        let (instr, params) = match self.mem.fetch(self.pc) {
+            (Some(instr), params) => (instr, params),
+            (None, params) => (self.decode()?, params),
+        };
+        match instr.op {
+            OpcodeType::Add => {
+                let input1 = self.read_parameter(instr, params, 0)?;
+                let input2 = self.read_parameter(instr, params, 1)?;
+                let sum = input1.checked_add(input2);
+                let sum = sum.ok_or_else(|| self.fault(Fault::Overflow))?;
+                self.write_parameter(instr, params, 2, sum)?;
+            }
+            OpcodeType::Mul => {
+                let input1 = self.read_parameter(instr, params, 0)?;
+                let input2 = self.read_parameter(instr, params, 1)?;
+                let product = input1.checked_mul(input2);
+                let product = product.ok_or_else(|| self.fault(Fault::Overflow))?;
+                self.write_parameter(instr, params, 2, product)?;
+            }
+            OpcodeType::Read => {
+                // a faulting destination leaves the input and history alone.
+                let addr = self.write_address(instr, params, 0)?;
+                if let Some(input) = input.next() {
+                    if self.history.is_some() {
+                        self.record_snapshot();
+                    }
+                    self.mem_set(addr, input);
+                } else {
+                    return Ok(Some(StopReason::NeedsInput));
+                }
+            }
+            OpcodeType::Write => {
+                let output = self.read_parameter(instr, params, 0)?;
+                self.pc += 2;
+                return Ok(Some(StopReason::Output(output)));
+            }
+            OpcodeType::JumpNotZero => {
+                if self.read_parameter(instr, params, 0)? != 0 {
+                    self.pc = self.address(self.read_parameter(instr, params, 1)?)?;
+                    return Ok(None);
+                }
+            }
+            OpcodeType::JumpZero => {
+                if self.read_parameter(instr, params, 0)? == 0 {
+                    self.pc = self.address(self.read_parameter(instr, params, 1)?)?;
+                    return Ok(None);
+                }
+            }
+            OpcodeType::LessThan => {
+                let input1 = self.read_parameter(instr, params, 0)?;
+                let input2 = self.read_parameter(instr, params, 1)?;
+                self.write_parameter(instr, params, 2, i64::from(input1 < input2))?;
+            }
+            OpcodeType::Equals => {
+                let input1 = self.read_parameter(instr, params, 0)?;
+                let input2 = self.read_parameter(instr, params, 1)?;
+                self.write_parameter(instr, params, 2, i64::from(input1 == input2))?;
+            }
+            OpcodeType::StackPtrAdd => {
+                let offset = self.read_parameter(instr, params, 0)?;
+                self.sp = self.relative(offset)?;
+            }
+            OpcodeType::Halt => return Ok(Some(StopReason::Halted)),
+        }
+        self.pc += instr.op.num_params() + 1;
+        Ok(None)
+    }
+
+    /// decode the instruction at the program counter and cache it.
+    #[cold]
+    fn decode(&mut self) -> Result<Decoded> {
+        let instr = Decoded::new(self.mem_get(self.pc)).map_err(|fault| self.fault(fault))?;
+        self.mem.set_decoded(self.pc, instr);
+        Ok(instr)
+    }
+    #[cold]
+    fn fault(&self, fault: Fault) -> Error {
+        Error::Machine {
+            pc: self.pc,
+            opcode: self.mem_get(self.pc),
+            fault,
+        }
+    }
+    #[inline(always)]
+    fn address(&self, addr: i64) -> Result<usize> {
+        usize::try_from(addr).map_err(|_| self.fault(Fault::NegativeAddress(addr)))
+    }
+
+    #[inline(always)]
+    fn relative(&self, offset: i64) -> Result<i64> {
+        self.sp
+            .checked_add(offset)
+            .ok_or_else(|| self.fault(Fault::Overflow))
+    }
+
+    #[inline(always)]
+    fn read_parameter(&self, instr: Decoded, params: [i64; 3], index: usize) -> Result<i64> {
+        let param = params[index];
+        match instr.modes[index] {
+            ParameterMode::Immediate => Ok(param),
+            ParameterMode::Position => Ok(self.mem_get(self.address(param)?)),
+            ParameterMode::Relative => Ok(self.mem_get(self.address(self.relative(param)?)?)),
+        }
+    }
+    #[inline(always)]
+    fn write_address(&self, instr: Decoded, params: [i64; 3], index: usize) -> Result<usize> {
+        let param = params[index];
+        match instr.modes[index] {
+            ParameterMode::Position => self.address(param),
+            ParameterMode::Relative => self.address(self.relative(param)?),
+            ParameterMode::Immediate => unreachable!("rejected when decoding"),
+        }
+    }
+    #[inline(always)]
+    fn write_parameter(
+        &mut self,
+        instr: Decoded,
+        params: [i64; 3],
+        index: usize,
+        value: i64,
+    ) -> Result<()> {
+        let addr = self.write_address(instr, params, index)?;
+        self.mem_set(addr, value);
+        Ok(())
+    }
+
+    /// Read any address. Memory past the end of the program reads as 0.
+    #[inline]
+    pub fn mem_get(&self, addr: usize) -> i64 {
+        self.mem.get(addr)
+    }
+    /// Write any address, growing memory to cover it.
+    #[inline]
+    pub fn mem_set(&mut self, addr: usize, value: i64) {
+        self.mem.set(addr, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewind() {
+        // print the running total of the inputs.
+        let program = "3,13,1,13,14,14,4,14,1105,1,0,99,0,0,0";
+        let mut machine = Machine::from_string(program).unwrap();
+        machine.set_history(2);
+        assert_eq!(
+            machine.run([1, 2, 3].iter().copied()).unwrap(),
+            vec![1, 3, 6]
+        );
+        assert_eq!(machine.history_len(), 2);
+
+        assert_eq!(machine.rewind(5), 2);
+        assert_eq!(machine.history_len(), 0);
+        assert_eq!(machine.run(std::iter::once(10)).unwrap(), vec![11]);
+        assert_eq!(machine.rewind(1), 1);
+        assert_eq!(machine.run(std::iter::once(20)).unwrap(), vec![21]);
+    }
+
+    #[test]
+    fn terminal() {
+        // echo every character typed.
+        let mut machine = Machine::from_string("3,7,4,7,1105,1,0,0").unwrap();
+        let mut printed = Vec::new();
+        let transcript = machine
+            .terminal("ab\n!back\nc\n".as_bytes(), &mut printed)
+            .unwrap();
+        assert_eq!(
+            transcript,
+            "ab\nc\n".bytes().map(i64::from).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            String::from_utf8(printed).unwrap(),
+            "ab\nwent back 1 line\nc\n"
+        );
+    }
+
+    #[test]
+    fn snapshot_and_restore() {
+        // print the running total of the inputs.
+        let program = "3,13,1,13,14,14,4,14,1105,1,0,99,0,0,0";
+        let mut machine = Machine::from_string(program).unwrap();
+        assert_eq!(machine.run(std::iter::once(5)).unwrap(), vec![5]);
+        let snapshot = machine.snapshot();
+
+        assert_eq!(machine.run([1, 2].iter().copied()).unwrap(), vec![6, 8]);
+        machine.mem_set(100, 1);
+        machine.restore(&snapshot);
+        assert_eq!(machine.mem_size(), 15);
+        assert_eq!(machine.run(std::iter::once(10)).unwrap(), vec![15]);
+    }
+
+    #[test]
+    fn invalid_opcode() {
+        let mut machine = Machine::from_string("1,0,0,0,42,0,99").unwrap();
+        let err = machine.run(std::iter::empty()).unwrap_err();
+        assert_eq!(err.to_string(), "invalid opcode at pc 4 (opcode 42)");
+        assert_eq!(machine.pc, 4);
+    }
+
+    #[test]
+    fn stop_reasons() {
+        // echo one number, then halt.
+        let mut machine = Machine::from_string("3,5,4,5,99,0").unwrap();
+        assert_eq!(
+            machine.resume(std::iter::empty()).unwrap(),
+            StopReason::NeedsInput
+        );
+        assert_eq!(machine.pc, 0);
+        assert_eq!(
+            machine.resume(std::iter::once(7)).unwrap(),
+            StopReason::Output(7)
+        );
+        assert_eq!(
+            machine.resume(std::iter::empty()).unwrap(),
+            StopReason::Halted
+        );
+        assert_eq!(
+            machine.resume(std::iter::empty()).unwrap(),
+            StopReason::Halted
+        );
+        assert!(machine.is_halted());
+    }
+
+    #[test]
+    fn run_until() {
+        // count down from 3, printing each number.
+        let mut machine = Machine::from_string("4,12,1001,12,-1,12,1005,12,0,99,0,0,3").unwrap();
+        let (output, reason) = machine
+            .run_until(std::iter::empty(), Limit::Outputs(2))
+            .unwrap();
+        assert_eq!(output, vec![3, 2]);
+        assert_eq!(reason, None);
+
+        let (output, reason) = machine
+            .run_until(std::iter::empty(), Limit::Instructions(1))
+            .unwrap();
+        assert!(output.is_empty());
+        assert_eq!(reason, None);
+
+        let (output, reason) = machine
+            .run_until(std::iter::empty(), Limit::Outputs(5))
+            .unwrap();
+        assert_eq!(output, vec![1]);
+        assert_eq!(reason, Some(StopReason::Halted));
+    }
+
+    #[test]
+    fn self_modifying() {
+        // overwrite the halt at 4 with an output before reaching it.
+        let mut machine = Machine::from_string("1101,104,0,4,99,7,99").unwrap();
+        assert_eq!(machine.run(std::iter::empty()).unwrap(), vec![7]);
+    }
+
+    #[test]
+    fn trailing_garbage() {
+        assert!(Machine::from_string("1,0,0,0,99,x").is_err());
+    }
+}
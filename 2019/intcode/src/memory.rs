@@ -0,0 +1,209 @@
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::machine::Decoded;
+
+const PAGE_SIZE: usize = 256;
+
+#[derive(Clone)]
+struct Page {
+    cells: [i64; PAGE_SIZE],
+    decoded: [Option<Decoded>; PAGE_SIZE],
+}
+impl Page {
+    fn zeroed() -> Self {
+        Page {
+            cells: [0; PAGE_SIZE],
+            decoded: [None; PAGE_SIZE],
+        }
+    }
+}
+
+/// A page is shared with the loaded program until it's written.
+#[derive(Clone)]
+enum Slot {
+    Shared(Arc<Page>),
+    Owned(Box<Page>),
+}
+impl Slot {
+    #[inline]
+    fn page(&self) -> &Page {
+        match self {
+            Slot::Shared(page) => page,
+            Slot::Owned(page) => page,
+        }
+    }
+}
+
+/// Intcode memory. Pages of the loaded program are shared by every clone,
+/// and a machine only copies a page once it writes to it, so cloning copies
+/// just the pages written so far. Every cell also caches its decoded
+/// instruction, which is dropped when the cell is written.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<i64>", into = "Vec<i64>")]
+pub struct Memory {
+    pages: Vec<Slot>,
+    len: usize,
+}
+impl Memory {
+    pub fn new(cells: Vec<i64>) -> Self {
+        let pages = cells
+            .chunks(PAGE_SIZE)
+            .map(|chunk| {
+                let mut page = Page::zeroed();
+                page.cells[..chunk.len()].copy_from_slice(chunk);
+                for (decoded, &opcode) in page.decoded.iter_mut().zip(chunk) {
+                    *decoded = Decoded::new(opcode).ok();
+                }
+                Slot::Shared(Arc::new(page))
+            })
+            .collect();
+        Memory {
+            pages,
+            len: cells.len(),
+        }
+    }
+    /// one past the highest address loaded or written.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    #[inline]
+    fn page(&self, addr: usize) -> Option<&Page> {
+        self.pages.get(addr / PAGE_SIZE).map(Slot::page)
+    }
+    #[inline]
+    fn page_mut(&mut self, addr: usize) -> &mut Page {
+        let index = addr / PAGE_SIZE;
+        self.len = self.len.max(addr + 1);
+        if !matches!(self.pages.get(index), Some(Slot::Owned(_))) {
+            self.copy_page(index);
+        }
+        match &mut self.pages[index] {
+            Slot::Owned(page) => page,
+            Slot::Shared(_) => unreachable!(),
+        }
+    }
+    /// give this memory its own copy of a page, kept out of line so that
+    /// writes to pages already copied stay cheap.
+    #[cold]
+    #[inline(never)]
+    fn copy_page(&mut self, index: usize) {
+        if index >= self.pages.len() {
+            let zeroed = Arc::new(Page::zeroed());
+            self.pages
+                .resize_with(index + 1, || Slot::Shared(zeroed.clone()));
+        }
+        if let Slot::Shared(page) = &self.pages[index] {
+            self.pages[index] = Slot::Owned(Box::new(Page::clone(page)));
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, addr: usize) -> i64 {
+        self.page(addr)
+            .map_or(0, |page| page.cells[addr % PAGE_SIZE])
+    }
+    #[inline]
+    pub fn set(&mut self, addr: usize, value: i64) {
+        let page = self.page_mut(addr);
+        page.cells[addr % PAGE_SIZE] = value;
+        page.decoded[addr % PAGE_SIZE] = None;
+    }
+    /// the cached decoded instruction at `addr` and the three cells after
+    /// it, the most an instruction's parameters take up.
+    #[inline]
+    pub(crate) fn fetch(&self, addr: usize) -> (Option<Decoded>, [i64; 3]) {
+        let offset = addr % PAGE_SIZE;
+        match self.page(addr) {
+            Some(page) if offset + 4 <= PAGE_SIZE => (
+                page.decoded[offset],
+                [
+                    page.cells[offset + 1],
+                    page.cells[offset + 2],
+                    page.cells[offset + 3],
+                ],
+            ),
+            page => (
+                page.and_then(|page| page.decoded[offset]),
+                [self.get(addr + 1), self.get(addr + 2), self.get(addr + 3)],
+            ),
+        }
+    }
+    pub(crate) fn set_decoded(&mut self, addr: usize, decoded: Decoded) {
+        self.page_mut(addr).decoded[addr % PAGE_SIZE] = Some(decoded);
+    }
+
+    /// a copy that shares every page with this memory, including the ones
+    /// written so far, which are copied again on their next write.
+    pub fn snapshot(&mut self) -> Memory {
+        self.pages = std::mem::take(&mut self.pages)
+            .into_iter()
+            .map(|slot| match slot {
+                Slot::Owned(page) => Slot::Shared(Arc::from(page)),
+                slot => slot,
+            })
+            .collect();
+        self.clone()
+    }
+
+    pub fn to_vec(&self) -> Vec<i64> {
+        (0..self.len).map(|addr| self.get(addr)).collect()
+    }
+    /// how many pages this memory has its own copy of.
+    pub fn written_pages(&self) -> usize {
+        self.pages
+            .iter()
+            .filter(|slot| matches!(slot, Slot::Owned(_)))
+            .count()
+    }
+}
+impl From<Vec<i64>> for Memory {
+    fn from(cells: Vec<i64>) -> Self {
+        Memory::new(cells)
+    }
+}
+impl From<Memory> for Vec<i64> {
+    fn from(memory: Memory) -> Self {
+        memory.to_vec()
+    }
+}
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.to_vec()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_on_write() {
+        let original = Memory::new((0..1000).collect());
+        let mut copy = original.clone();
+        assert_eq!(copy.written_pages(), 0);
+
+        copy.set(300, -1);
+        copy.set(5000, 7);
+        assert_eq!(copy.written_pages(), 2);
+        assert_eq!(original.written_pages(), 0);
+        assert_eq!(original.get(300), 300);
+        assert_eq!(copy.get(300), -1);
+        assert_eq!(copy.get(301), 301);
+        assert_eq!(copy.get(5000), 7);
+        assert_eq!(copy.get(4999), 0);
+        assert_eq!(copy.len(), 5001);
+        assert_eq!(original.len(), 1000);
+
+        let snapshot = copy.snapshot();
+        assert_eq!(copy.written_pages(), 0);
+        copy.set(300, -2);
+        assert_eq!(snapshot.get(300), -1);
+        assert_eq!(copy.written_pages(), 1);
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::disassembler::{decode_with, mnemonic, Operand};
+use crate::error::Result;
+use crate::machine::Machine;
+
+/// A tracer shared by a machine and all of its clones.
+pub type SharedTracer = Arc<Mutex<Tracer>>;
+
+/// Counts executed instructions, and optionally logs each one.
+#[derive(Default)]
+pub struct Tracer {
+    log: Option<Box<dyn Write + Send>>,
+    log_error: Option<io::Error>,
+    hits: HashMap<usize, u64>,
+    opcodes: BTreeMap<&'static str, u64>,
+}
+impl Tracer {
+    /// profile without logging.
+    pub fn new() -> Self {
+        Tracer::default()
+    }
+    pub fn with_log(log: Box<dyn Write + Send>) -> Self {
+        Tracer {
+            log: Some(log),
+            ..Tracer::default()
+        }
+    }
+    pub fn to_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        Ok(Tracer::with_log(Box::new(file)))
+    }
+    pub fn shared(self) -> SharedTracer {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// how many times the instruction at each address ran.
+    pub fn hits(&self) -> &HashMap<usize, u64> {
+        &self.hits
+    }
+    /// how many times each instruction ran, by mnemonic.
+    pub fn opcode_counts(&self) -> &BTreeMap<&'static str, u64> {
+        &self.opcodes
+    }
+    pub fn total(&self) -> u64 {
+        self.opcodes.values().sum()
+    }
+    /// the `count` most executed addresses, most executed first.
+    pub fn hottest(&self, count: usize) -> Vec<(usize, u64)> {
+        let mut hits: Vec<_> = self.hits.iter().map(|(&pc, &hits)| (pc, hits)).collect();
+        hits.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        hits.truncate(count);
+        hits
+    }
+
+    /// Flush the log, reporting the first error writing it.
+    pub fn finish(&mut self) -> Result<()> {
+        if let Some(err) = self.log_error.take() {
+            return Err(err.into());
+        }
+        if let Some(log) = self.log.as_mut() {
+            log.flush()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn record(&mut self, entry: Entry, machine: &Machine) {
+        *self.hits.entry(entry.pc).or_insert(0) += 1;
+        *self.opcodes.entry(entry.mnemonic).or_insert(0) += 1;
+
+        let log = match self.log.as_mut() {
+            Some(log) if self.log_error.is_none() => log,
+            _ => return,
+        };
+        let result = match entry.write {
+            Some(addr) => writeln!(
+                log,
+                "{:5}: {} ; [{}] <- {}",
+                entry.pc,
+                entry.text,
+                addr,
+                machine.mem_get(addr)
+            ),
+            None => writeln!(log, "{:5}: {}", entry.pc, entry.text),
+        };
+        if let Err(err) = result {
+            self.log_error = Some(err);
+        }
+    }
+}
+impl fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tracer")
+            .field("logging", &self.log.is_some())
+            .field("total", &self.total())
+            .finish()
+    }
+}
+/// a short profile, the opcode counts and the hottest addresses.
+impl fmt::Display for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} instructions", self.total())?;
+        for (mnemonic, count) in self.opcodes.iter() {
+            writeln!(f, "  {:4} {}", mnemonic, count)?;
+        }
+        writeln!(f, "hottest addresses")?;
+        for (pc, hits) in self.hottest(20) {
+            writeln!(f, "  {:5}: {}", pc, hits)?;
+        }
+        Ok(())
+    }
+}
+
+/// An instruction about to be executed, with its operands resolved against
+/// the machine before it runs.
+pub(crate) struct Entry {
+    pc: usize,
+    mnemonic: &'static str,
+    text: String,
+    write: Option<usize>,
+}
+impl Entry {
+    /// `None` if the machine is about to fault.
+    pub(crate) fn new(machine: &Machine) -> Option<Entry> {
+        let instruction = decode_with(|addr| Some(machine.mem_get(addr)), machine.pc())?;
+        let address = |operand: &Operand| match *operand {
+            Operand::Position(addr) => usize::try_from(addr).ok(),
+            Operand::Immediate(_) => None,
+            Operand::Relative(offset) => usize::try_from(machine.sp().checked_add(offset)?).ok(),
+        };
+
+        let mut text = mnemonic(instruction.op).to_string();
+        let mut write = None;
+        for (i, operand) in instruction.operands.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            let is_write = instruction.op.writes() && i + 1 == instruction.operands.len();
+            let resolved = match (operand, address(operand)) {
+                (Operand::Immediate(_), _) => operand.to_string(),
+                (Operand::Position(_), Some(addr)) if is_write => format!("[{}]", addr),
+                (Operand::Position(_), Some(addr)) => {
+                    format!("[{}]={}", addr, machine.mem_get(addr))
+                }
+                (Operand::Relative(_), Some(addr)) if is_write => format!("{}[{}]", operand, addr),
+                (Operand::Relative(_), Some(addr)) => {
+                    format!("{}[{}]={}", operand, addr, machine.mem_get(addr))
+                }
+                (_, None) => return None,
+            };
+            if is_write {
+                write = address(operand);
+            }
+            text.push_str(separator);
+            text.push_str(&resolved);
+        }
+        Some(Entry {
+            pc: instruction.address,
+            mnemonic: mnemonic(instruction.op),
+            text,
+            write,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    #[test]
+    fn profile_and_log() {
+        let program = assemble(
+            "
+            loop: out rb+count
+                  add rb+count, #-1, rb+count
+                  jnz [count], #loop
+                  hlt
+            count: data 2
+        ",
+        )
+        .unwrap();
+        let name = format!("advent-of-code-trace-{}.txt", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        let tracer = Tracer::to_file(&path).unwrap().shared();
+
+        let mut machine = Machine::new(program).with_tracer(tracer.clone());
+        assert_eq!(machine.clone().run(std::iter::empty()).unwrap(), vec![2, 1]);
+        machine.run(std::iter::empty()).unwrap();
+
+        let mut tracer = tracer.lock().unwrap();
+        tracer.finish().unwrap();
+        assert_eq!(tracer.total(), 12);
+        assert_eq!(tracer.opcode_counts()["add"], 4);
+        assert_eq!(tracer.hottest(1), vec![(0, 4)]);
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<_> = log.lines().take(3).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "    0: out rb+10[10]=2",
+                "    2: add rb+10[10]=2, #-1, rb+10[10] ; [10] <- 1",
+                "    6: jnz [10]=1, #0",
+            ]
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! What every Intcode machine has to do, from the puzzle descriptions of
+//! days 2, 5 and 9.
+
+use intcode::{Error, Fault, Limit, Machine, StopReason};
+
+const POSITION: i64 = 0;
+const IMMEDIATE: i64 = 1;
+const RELATIVE: i64 = 2;
+
+/// the relative base the generated programs set before anything else.
+const BASE: i64 = 40;
+
+type Operation = fn(i64, i64) -> i64;
+type Predicate = fn(i64) -> bool;
+
+fn opcode(op: i64, modes: &[i64]) -> i64 {
+    modes
+        .iter()
+        .zip([100, 1000, 10000].iter())
+        .fold(op, |opcode, (mode, place)| opcode + mode * place)
+}
+
+fn run(program: &[i64], input: &[i64]) -> Vec<i64> {
+    Machine::new(program.to_vec())
+        .run(input.iter().copied())
+        .unwrap()
+}
+
+fn fault(program: &[i64]) -> Fault {
+    match Machine::new(program.to_vec()).run(std::iter::empty()) {
+        Err(Error::Machine { fault, .. }) => fault,
+        result => panic!("expected a fault, got {:?}", result),
+    }
+}
+
+/// a parameter for `value` in `mode`, keeping the value at `addr` if it
+/// has to be in memory.
+fn parameter(machine: &mut Machine, mode: i64, value: i64, addr: i64) -> i64 {
+    match mode {
+        POSITION => {
+            machine.mem_set(addr as usize, value);
+            addr
+        }
+        IMMEDIATE => value,
+        _ => {
+            machine.mem_set(addr as usize, value);
+            addr - BASE
+        }
+    }
+}
+
+#[test]
+fn arithmetic_and_comparison() {
+    let ops: [(i64, Operation); 4] = [
+        (1, |a, b| a + b),
+        (2, |a, b| a * b),
+        (7, |a, b| i64::from(a < b)),
+        (8, |a, b| i64::from(a == b)),
+    ];
+    let pairs = [(3, 4), (-5, 7), (6, 6), (0, -1)];
+    for &(op, expected) in ops.iter() {
+        for &(a, b) in pairs.iter() {
+            for &mode_a in [POSITION, IMMEDIATE, RELATIVE].iter() {
+                for &mode_b in [POSITION, IMMEDIATE, RELATIVE].iter() {
+                    for &mode_c in [POSITION, RELATIVE].iter() {
+                        // arb #BASE, then the instruction, then hlt.
+                        let mut machine = Machine::new(vec![109, BASE, 0, 0, 0, 0, 99]);
+                        let param_a = parameter(&mut machine, mode_a, a, 50);
+                        let param_b = parameter(&mut machine, mode_b, b, 51);
+                        let param_c = parameter(&mut machine, mode_c, -1, 60);
+                        let instruction = [
+                            opcode(op, &[mode_a, mode_b, mode_c]),
+                            param_a,
+                            param_b,
+                            param_c,
+                        ];
+                        for (addr, &value) in (2..).zip(instruction.iter()) {
+                            machine.mem_set(addr, value);
+                        }
+                        machine.run(std::iter::empty()).unwrap();
+                        assert!(machine.is_halted());
+                        assert_eq!(
+                            machine.mem_get(60),
+                            expected(a, b),
+                            "{:?} with a = {}, b = {}",
+                            instruction,
+                            a,
+                            b
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn input_and_output() {
+    // in [5], out [5], hlt
+    assert_eq!(run(&[3, 5, 4, 5, 99, 0], &[42]), vec![42]);
+    // arb #10, in rb-3, out rb-3, hlt
+    assert_eq!(run(&[109, 10, 203, -3, 204, -3, 99, 0], &[-42]), vec![-42]);
+    // out #17, out [0], arb #2, out rb+1
+    assert_eq!(
+        run(&[104, 17, 4, 0, 109, 2, 204, 1, 99], &[]),
+        vec![17, 104, 0]
+    );
+}
+
+#[test]
+fn jumps() {
+    // out #1, jump if the condition says so, out #2, then out #3.
+    for &(op, condition, taken) in
+        [(5, 1, true), (5, 0, false), (6, 0, true), (6, -3, false)].iter()
+    {
+        for &mode_condition in [POSITION, IMMEDIATE, RELATIVE].iter() {
+            for &mode_target in [POSITION, IMMEDIATE, RELATIVE].iter() {
+                let mut machine =
+                    Machine::new(vec![109, BASE, 104, 1, 0, 0, 0, 104, 2, 104, 3, 99]);
+                let param_condition = parameter(&mut machine, mode_condition, condition, 50);
+                let param_target = parameter(&mut machine, mode_target, 9, 51);
+                machine.mem_set(4, opcode(op, &[mode_condition, mode_target]));
+                machine.mem_set(5, param_condition);
+                machine.mem_set(6, param_target);
+                let output = machine.run(std::iter::empty()).unwrap();
+                let expected = if taken { vec![1, 3] } else { vec![1, 2, 3] };
+                assert_eq!(output, expected, "opcode {}", machine.mem_get(4));
+            }
+        }
+    }
+}
+
+#[test]
+fn relative_base() {
+    // arb #5, arb #-2, arb [0]: the base is 5 - 2 + 109 = 112.
+    let mut machine = Machine::from_string("109,5,109,-2,9,0,99").unwrap();
+    machine.run(std::iter::empty()).unwrap();
+    assert_eq!(machine.sp(), 112);
+
+    // arb rb+5 adds the cell at the current base plus 5.
+    let mut machine = Machine::from_string("109,3,209,5,99,0,0,0,20").unwrap();
+    machine.run(std::iter::empty()).unwrap();
+    assert_eq!(machine.sp(), 23);
+
+    // the example from day 9: arb #19, then out rb-34 reads 1985.
+    let mut machine = Machine::from_string("109,19,204,-34,99").unwrap();
+    machine.set_registers(0, 2000);
+    machine.mem_set(1985, 7);
+    assert_eq!(machine.run(std::iter::empty()).unwrap(), vec![7]);
+    assert_eq!(machine.sp(), 2019);
+}
+
+#[test]
+fn large_numbers() {
+    // the other two day 9 examples.
+    assert_eq!(
+        run(&[1102, 34_915_192, 34_915_192, 7, 4, 7, 99, 0], &[]),
+        vec![1_219_070_632_396_864]
+    );
+    assert_eq!(
+        run(&[104, 1_125_899_906_842_624, 99], &[]),
+        vec![1_125_899_906_842_624]
+    );
+
+    // mul #n, #n, [7] with a result just below i64::MAX.
+    let n = 3_037_000_499;
+    assert_eq!(run(&[1102, n, n, 7, 4, 7, 99, 0], &[]), vec![n * n]);
+
+    // add #big, #big, [11], add [11], #-big, [11], out [11]
+    let big = i64::MAX / 2;
+    let program = [1101, big, big, 11, 1001, 11, -big, 11, 4, 11, 99, 0];
+    assert_eq!(run(&program, &[]), vec![big]);
+
+    // the extremes go through input, output and comparisons unchanged.
+    assert_eq!(run(&[3, 7, 4, 7, 99, 0, 0, 0], &[i64::MIN]), vec![i64::MIN]);
+    assert_eq!(
+        run(&[1107, i64::MIN, i64::MAX, 7, 4, 7, 99, 0], &[]),
+        vec![1]
+    );
+}
+
+#[test]
+fn memory_growth() {
+    // add #1, #2, [10000], then out [10000] and out [5000].
+    let mut machine = Machine::from_string("1101,1,2,10000,4,10000,4,5000,99").unwrap();
+    assert_eq!(machine.mem_size(), 9);
+    assert_eq!(machine.run(std::iter::empty()).unwrap(), vec![3, 0]);
+    assert_eq!(machine.mem_size(), 10001);
+    assert_eq!(machine.mem_get(10000), 3);
+    assert_eq!(machine.mem_get(1_000_000), 0);
+
+    // relative writes grow memory too: arb #3000, in rb+7, out [3007].
+    assert_eq!(run(&[109, 3000, 203, 7, 4, 3007, 99], &[9]), vec![9]);
+
+    // reading far past the end doesn't grow memory.
+    let mut machine = Machine::from_string("4,100000,99").unwrap();
+    assert_eq!(machine.run(std::iter::empty()).unwrap(), vec![0]);
+    assert_eq!(machine.mem_size(), 3);
+}
+
+#[test]
+fn starvation_and_resumption() {
+    // in [13], in [14], add [13], [14], [15], out [15], hlt
+    let program = "3,13,3,14,1,13,14,15,4,15,99,0,0,0,0,0";
+    let mut machine = Machine::from_string(program).unwrap();
+
+    // waiting for input leaves the machine at the input instruction.
+    assert_eq!(machine.run(std::iter::empty()).unwrap(), vec![]);
+    assert_eq!(machine.pc(), 0);
+    assert!(!machine.is_halted());
+    assert_eq!(
+        machine.step(std::iter::empty()).unwrap(),
+        Some(StopReason::NeedsInput)
+    );
+    assert_eq!(machine.pc(), 0);
+
+    assert_eq!(machine.run(std::iter::once(4)).unwrap(), vec![]);
+    assert_eq!(machine.pc(), 2);
+    assert_eq!(
+        machine.resume(std::iter::empty()).unwrap(),
+        StopReason::NeedsInput
+    );
+    assert_eq!(
+        machine.resume(std::iter::once(5)).unwrap(),
+        StopReason::Output(9)
+    );
+    assert_eq!(
+        machine.resume(std::iter::empty()).unwrap(),
+        StopReason::Halted
+    );
+    assert!(machine.is_halted());
+
+    // unread input is left in the iterator.
+    let mut input = vec![1, 2, 3].into_iter();
+    let mut machine = Machine::from_string(program).unwrap();
+    assert_eq!(machine.run(&mut input).unwrap(), vec![3]);
+    assert_eq!(input.next(), Some(3));
+
+    // a limit stops between instructions, without losing input.
+    let mut machine = Machine::from_string(program).unwrap();
+    let (output, reason) = machine
+        .run_until(std::iter::once(1), Limit::Instructions(1))
+        .unwrap();
+    assert_eq!((output, reason), (vec![], None));
+    let (output, reason) = machine
+        .run_until(std::iter::once(1), Limit::Outputs(1))
+        .unwrap();
+    assert_eq!((output, reason), (vec![2], None));
+}
+
+#[test]
+fn faults() {
+    assert_eq!(fault(&[42]), Fault::InvalidOpcode);
+    assert_eq!(fault(&[301, 0, 0, 0, 99]), Fault::InvalidParameterMode(3));
+    assert_eq!(fault(&[11101, 1, 1, 0, 99]), Fault::WriteToImmediate);
+    assert_eq!(fault(&[103, 0, 99]), Fault::WriteToImmediate);
+    assert_eq!(fault(&[4, -1, 99]), Fault::NegativeAddress(-1));
+    assert_eq!(fault(&[109, -5, 204, 0, 99]), Fault::NegativeAddress(-5));
+    assert_eq!(fault(&[1105, 1, -2]), Fault::NegativeAddress(-2));
+    assert_eq!(fault(&[1101, i64::MAX, 1, 0, 99]), Fault::Overflow);
+    assert_eq!(fault(&[1102, i64::MIN, -1, 0, 99]), Fault::Overflow);
+    assert_eq!(fault(&[109, i64::MAX, 109, 1, 99]), Fault::Overflow);
+    assert_eq!(fault(&[109, 1, 204, i64::MAX, 99]), Fault::Overflow);
+
+    // a read with a bad destination doesn't take any input.
+    let mut input = vec![7].into_iter();
+    let mut machine = Machine::new(vec![3, -1, 99]);
+    assert!(machine.run(&mut input).is_err());
+    assert_eq!(input.next(), Some(7));
+
+    // the machine stays on the faulting instruction.
+    let mut machine = Machine::from_string("104,1,42").unwrap();
+    assert!(machine.run(std::iter::empty()).is_err());
+    assert_eq!(machine.pc(), 2);
+}
+
+#[test]
+fn day02_examples() {
+    let examples = [
+        (
+            "1,9,10,3,2,3,11,0,99,30,40,50",
+            "3500,9,10,70,2,3,11,0,99,30,40,50",
+        ),
+        ("1,0,0,0,99", "2,0,0,0,99"),
+        ("2,3,0,3,99", "2,3,0,6,99"),
+        ("2,4,4,5,99,0", "2,4,4,5,99,9801"),
+        ("1,1,1,4,99,5,6,0,99", "30,1,1,4,2,5,6,0,99"),
+    ];
+    for &(program, expected) in examples.iter() {
+        let mut machine = Machine::from_string(program).unwrap();
+        machine.run(std::iter::empty()).unwrap();
+        assert_eq!(
+            machine.memory(),
+            Machine::from_string(expected).unwrap().memory()
+        );
+    }
+}
+
+#[test]
+fn day05_examples() {
+    // negative numbers and modes.
+    let mut machine = Machine::from_string("1002,4,3,4,33").unwrap();
+    machine.run(std::iter::empty()).unwrap();
+    assert_eq!(machine.mem_get(4), 99);
+    let mut machine = Machine::from_string("1101,100,-1,4,0").unwrap();
+    machine.run(std::iter::empty()).unwrap();
+    assert_eq!(machine.mem_get(4), 99);
+
+    // equal to and less than 8, in position and immediate mode.
+    let comparisons: [(&str, Predicate); 4] = [
+        ("3,9,8,9,10,9,4,9,99,-1,8", |x| x == 8),
+        ("3,9,7,9,10,9,4,9,99,-1,8", |x| x < 8),
+        ("3,3,1108,-1,8,3,4,3,99", |x| x == 8),
+        ("3,3,1107,-1,8,3,4,3,99", |x| x < 8),
+    ];
+    // whether the input is non-zero, by jumps.
+    let jumps: [&str; 2] = [
+        "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+        "3,3,1105,-1,9,1101,0,0,12,4,12,99,1",
+    ];
+    // 999 below 8, 1000 for 8 and 1001 above.
+    let larger = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,\
+                  1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,\
+                  1105,1,46,98,99";
+    for input in -2..12 {
+        for &(program, expected) in comparisons.iter() {
+            let output = Machine::from_string(program)
+                .unwrap()
+                .run(std::iter::once(input))
+                .unwrap();
+            assert_eq!(
+                output,
+                vec![i64::from(expected(input))],
+                "{} with {}",
+                program,
+                input
+            );
+        }
+        for program in jumps.iter() {
+            let output = Machine::from_string(program)
+                .unwrap()
+                .run(std::iter::once(input))
+                .unwrap();
+            assert_eq!(
+                output,
+                vec![i64::from(input != 0)],
+                "{} with {}",
+                program,
+                input
+            );
+        }
+        let output = Machine::from_string(larger)
+            .unwrap()
+            .run(std::iter::once(input))
+            .unwrap();
+        assert_eq!(output, vec![999 + (input.cmp(&8) as i64) + 1]);
+    }
+}
+
+#[test]
+fn day09_examples() {
+    let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    let program = Machine::from_string(quine).unwrap().memory();
+    assert_eq!(run(&program, &[]), program);
+}
+
+#[test]
+fn puzzle_inputs() {
+    let load = |input: &str| Machine::from_string(input).unwrap();
+    let day05 = include_str!("data/day05.txt");
+    let day09 = include_str!("data/day09.txt");
+
+    // every diagnostic test passes, printing 0, before the code.
+    let output = load(day05).run(std::iter::once(1)).unwrap();
+    let (code, tests) = output.split_last().unwrap();
+    assert!(tests.iter().all(|&test| test == 0));
+    assert_eq!(*code, 13_787_043);
+    assert_eq!(
+        load(day05).run(std::iter::once(5)).unwrap(),
+        vec![3_892_695]
+    );
+
+    // BOOST only prints the keycode if no opcode malfunctioned.
+    assert_eq!(
+        load(day09).run(std::iter::once(1)).unwrap(),
+        vec![3_345_854_957]
+    );
+    assert_eq!(load(day09).run(std::iter::once(2)).unwrap(), vec![68938]);
+}
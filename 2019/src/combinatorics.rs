@@ -0,0 +1,292 @@
+use crate::error::{Error, Result};
+
+/// Every ordering of `items` in lexicographic order of their positions, so
+/// sorted items give sorted permutations.
+pub fn permutations<T: Clone>(items: &[T]) -> Permutations<T> {
+    Permutations {
+        items: items.to_vec(),
+        indices: (0..items.len()).collect(),
+        done: false,
+    }
+}
+
+pub struct Permutations<T> {
+    items: Vec<T>,
+    indices: Vec<usize>,
+    done: bool,
+}
+impl<T: Clone> Iterator for Permutations<T> {
+    type Item = Vec<T>;
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.done {
+            return None;
+        }
+        let permutation = self
+            .indices
+            .iter()
+            .map(|&index| self.items[index].clone())
+            .collect();
+
+        // the next permutation: swap the last ascent with the smallest larger
+        // index after it, and reverse the tail.
+        match self.indices.windows(2).rposition(|pair| pair[0] < pair[1]) {
+            Some(pivot) => {
+                let larger = self
+                    .indices
+                    .iter()
+                    .rposition(|&index| index > self.indices[pivot])
+                    .unwrap();
+                self.indices.swap(pivot, larger);
+                self.indices[pivot + 1..].reverse();
+            }
+            None => self.done = true,
+        }
+        Some(permutation)
+    }
+}
+
+/// Every ordering of `items` by Heap's algorithm, each one a single swap
+/// from the one before.
+pub fn heap_permutations<T: Clone>(items: &[T]) -> HeapPermutations<T> {
+    HeapPermutations {
+        items: items.to_vec(),
+        counters: vec![0; items.len()],
+        index: 0,
+        started: false,
+    }
+}
+
+pub struct HeapPermutations<T> {
+    items: Vec<T>,
+    counters: Vec<usize>,
+    index: usize,
+    started: bool,
+}
+impl<T: Clone> Iterator for HeapPermutations<T> {
+    type Item = Vec<T>;
+    fn next(&mut self) -> Option<Vec<T>> {
+        if !self.started {
+            self.started = true;
+            return Some(self.items.clone());
+        }
+        while self.index < self.items.len() {
+            if self.counters[self.index] < self.index {
+                if self.index % 2 == 0 {
+                    self.items.swap(0, self.index);
+                } else {
+                    self.items.swap(self.counters[self.index], self.index);
+                }
+                self.counters[self.index] += 1;
+                self.index = 0;
+                return Some(self.items.clone());
+            }
+            self.counters[self.index] = 0;
+            self.index += 1;
+        }
+        None
+    }
+}
+
+/// Every way of choosing `k` of `items`, keeping their order, in
+/// lexicographic order of their positions.
+pub fn combinations<T: Clone>(items: &[T], k: usize) -> Combinations<T> {
+    Combinations {
+        items: items.to_vec(),
+        indices: (0..k).collect(),
+        done: k > items.len(),
+    }
+}
+
+pub struct Combinations<T> {
+    items: Vec<T>,
+    indices: Vec<usize>,
+    done: bool,
+}
+impl<T: Clone> Iterator for Combinations<T> {
+    type Item = Vec<T>;
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.done {
+            return None;
+        }
+        let combination = self
+            .indices
+            .iter()
+            .map(|&index| self.items[index].clone())
+            .collect();
+
+        // move the last index that can move up, and the ones after it just
+        // behind it.
+        let (n, k) = (self.items.len(), self.indices.len());
+        match (0..k).rev().find(|&i| self.indices[i] < n - k + i) {
+            Some(i) => {
+                self.indices[i] += 1;
+                for j in i + 1..k {
+                    self.indices[j] = self.indices[j - 1] + 1;
+                }
+            }
+            None => self.done = true,
+        }
+        Some(combination)
+    }
+}
+
+/// Every subset of `items`, keeping their order, in gray code order: each
+/// subset adds or removes one item from the one before, starting from the
+/// empty set. The subsets are counted in a `u64`, so at most
+/// `MAX_POWER_SET_ITEMS` items are allowed.
+pub fn power_set<T: Clone>(items: &[T]) -> Result<PowerSet<T>> {
+    if items.len() > MAX_POWER_SET_ITEMS {
+        return Err(Error::no_solution(format!(
+            "{} items is too many for a power set, the most is {}",
+            items.len(),
+            MAX_POWER_SET_ITEMS
+        )));
+    }
+    Ok(PowerSet {
+        items: items.to_vec(),
+        step: 0,
+    })
+}
+
+pub const MAX_POWER_SET_ITEMS: usize = 63;
+
+pub struct PowerSet<T> {
+    items: Vec<T>,
+    step: u64,
+}
+impl<T: Clone> Iterator for PowerSet<T> {
+    type Item = Vec<T>;
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.step >> self.items.len() != 0 {
+            return None;
+        }
+        let gray = self.step ^ (self.step >> 1);
+        self.step += 1;
+        Some(
+            self.items
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| gray & 1 << index != 0)
+                .map(|(_, item)| item.clone())
+                .collect(),
+        )
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = ((1_u64 << self.items.len()) - self.step) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+/// Every way of picking one item from each of `sets`, the last set
+/// changing fastest.
+pub fn cartesian_product<T: Clone>(sets: &[Vec<T>]) -> CartesianProduct<T> {
+    CartesianProduct {
+        sets: sets.to_vec(),
+        indices: vec![0; sets.len()],
+        done: sets.iter().any(Vec::is_empty),
+    }
+}
+
+pub struct CartesianProduct<T> {
+    sets: Vec<Vec<T>>,
+    indices: Vec<usize>,
+    done: bool,
+}
+impl<T: Clone> Iterator for CartesianProduct<T> {
+    type Item = Vec<T>;
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.done {
+            return None;
+        }
+        let product = self
+            .indices
+            .iter()
+            .zip(self.sets.iter())
+            .map(|(&index, set)| set[index].clone())
+            .collect();
+
+        self.done = true;
+        for (index, set) in self.indices.iter_mut().zip(self.sets.iter()).rev() {
+            *index += 1;
+            if *index < set.len() {
+                self.done = false;
+                break;
+            }
+            *index = 0;
+        }
+        Some(product)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn lexicographic() {
+        let all: Vec<_> = permutations(&[1, 2, 3]).collect();
+        let expected = vec![
+            vec![1, 2, 3],
+            vec![1, 3, 2],
+            vec![2, 1, 3],
+            vec![2, 3, 1],
+            vec![3, 1, 2],
+            vec![3, 2, 1],
+        ];
+        assert_eq!(all, expected);
+        assert_eq!(permutations(&[0; 6]).count(), 720);
+        assert_eq!(permutations::<u8>(&[]).count(), 1);
+    }
+
+    #[test]
+    fn heap() {
+        let all: Vec<_> = heap_permutations(&['a', 'b', 'c', 'd']).collect();
+        assert_eq!(all.len(), 24);
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 24);
+        for pair in all.windows(2) {
+            let changed = pair[0].iter().zip(&pair[1]).filter(|(a, b)| a != b);
+            assert_eq!(changed.count(), 2);
+        }
+    }
+
+    #[test]
+    fn choose() {
+        let all: Vec<_> = combinations(&[1, 2, 3, 4], 2).collect();
+        let expected = vec![
+            vec![1, 2],
+            vec![1, 3],
+            vec![1, 4],
+            vec![2, 3],
+            vec![2, 4],
+            vec![3, 4],
+        ];
+        assert_eq!(all, expected);
+        assert_eq!(combinations(&[1, 2], 0).count(), 1);
+        assert_eq!(combinations(&[1, 2], 3).count(), 0);
+        assert_eq!(combinations(&[0; 10], 4).count(), 210);
+    }
+
+    #[test]
+    fn subsets() {
+        let all: Vec<_> = power_set(&[1, 2, 3]).unwrap().collect();
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], Vec::<i32>::new());
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 8);
+        for pair in all.windows(2) {
+            let (a, b) = (pair[0].len() as i32, pair[1].len() as i32);
+            assert_eq!((a - b).abs(), 1);
+        }
+        assert!(power_set(&[0; MAX_POWER_SET_ITEMS]).is_ok());
+        assert!(power_set(&[0; 64]).is_err());
+    }
+
+    #[test]
+    fn product() {
+        let all: Vec<_> = cartesian_product(&[vec![1, 2], vec![3], vec![4, 5]]).collect();
+        let expected = vec![vec![1, 3, 4], vec![1, 3, 5], vec![2, 3, 4], vec![2, 3, 5]];
+        assert_eq!(all, expected);
+        assert_eq!(cartesian_product(&[vec![1], vec![]]).count(), 0);
+        assert_eq!(cartesian_product::<i32>(&[]).count(), 1);
+    }
+}
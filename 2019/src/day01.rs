@@ -1,15 +1,17 @@
-use std::io;
-use std::io::Read;
-
 use nom::character::complete::{digit1, newline, space0};
+use nom::combinator::map_res;
 use nom::multi::separated_list;
 use nom::IResult;
 
+use crate::error::Result;
+use crate::parsers::complete_nom;
+use crate::solution::{Answer, Solution};
+
 fn line_p(input: &str) -> IResult<&str, u64> {
     let (input, _) = space0(input)?;
-    let (input, num_str) = digit1(input)?;
+    let (input, mass) = map_res(digit1, |s: &str| s.parse())(input)?;
     let (input, _) = space0(input)?;
-    Ok((input, num_str.parse().unwrap()))
+    Ok((input, mass))
 }
 
 fn lines_p(input: &str) -> IResult<&str, Vec<u64>> {
@@ -17,7 +19,9 @@ fn lines_p(input: &str) -> IResult<&str, Vec<u64>> {
 }
 
 pub fn part1(masses: Vec<u64>) -> u64 {
-    masses.into_iter().fold(0, |acc, mass| acc + (mass / 3) - 2)
+    masses
+        .into_iter()
+        .fold(0, |acc, mass| acc + (mass / 3).saturating_sub(2))
 }
 
 pub fn part2(masses: Vec<u64>) -> u64 {
@@ -35,12 +39,29 @@ pub fn part2(masses: Vec<u64>) -> u64 {
     })
 }
 
-pub fn start() {
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer).unwrap();
+pub struct Day01;
+impl Solution for Day01 {
+    type Input<'a> = Vec<u64>;
+
+    fn parse(input: &str) -> Result<Vec<u64>> {
+        complete_nom(lines_p(input), "one mass per line")
+    }
+    fn part1(masses: &Vec<u64>) -> Result<Answer> {
+        Ok(part1(masses.clone()).into())
+    }
+    fn part2(masses: &Vec<u64>) -> Result<Answer> {
+        Ok(part2(masses.clone()).into())
+    }
+}
 
-    let masses = lines_p(&buffer).unwrap().1;
-    let total_fuel = part2(masses);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    println!("Total fuel: {}", total_fuel);
+    #[test]
+    fn part1_examples() {
+        assert_eq!(part1(vec![12, 14, 1969, 100756]), 2 + 2 + 654 + 33583);
+        // too light to need any fuel.
+        assert_eq!(part1(vec![0, 1, 5, 8]), 0);
+    }
 }
@@ -1,69 +1,249 @@
-use std::io;
-use std::io::Read;
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryFrom;
 
-use nom::character::complete::{char, digit1};
-use nom::multi::separated_list;
-use nom::IResult;
+use intcode::disassembler::{decode_with, Operand};
+use intcode::tracer::SharedTracer;
+use intcode::{Limit, Machine, OpcodeType, StopReason};
 
-fn number_p(input: &str) -> IResult<&str, usize> {
-    let (input, num_str) = digit1(input)?;
-    Ok((input, num_str.parse().unwrap()))
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
+
+const NOUN: usize = 1;
+const VERB: usize = 2;
+const TARGET: i64 = 19_690_720;
+
+/// Run the program with `noun` and `verb` patched in, returning what's left
+/// in address 0.
+pub fn run(machine: &Machine, noun: i64, verb: i64) -> Result<i64> {
+    let mut machine = machine.clone();
+    machine.mem_set(NOUN, noun);
+    machine.mem_set(VERB, verb);
+    machine.run(std::iter::empty())?;
+    if !machine.is_halted() {
+        return Err(Error::no_solution("the program wanted input"));
+    }
+    Ok(machine.mem_get(0))
 }
 
-fn program_p(input: &str) -> IResult<&str, Vec<usize>> {
-    separated_list(char(','), number_p)(input)
+/// Try every noun and verb from 0 to 99 until address 0 ends up as
+/// `target`.
+pub fn search(machine: &Machine, target: i64) -> Result<(i64, i64)> {
+    for noun in 0..100 {
+        for verb in 0..100 {
+            if run(machine, noun, verb)? == target {
+                return Ok((noun, verb));
+            }
+        }
+    }
+    Err(Error::no_solution(format!("nothing gives {}", target)))
+}
+
+/// Address 0 as `constant + self.noun * noun + self.verb * verb`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Linear {
+    pub constant: i64,
+    pub noun: i64,
+    pub verb: i64,
 }
+impl Linear {
+    /// `None` if it overflows.
+    pub fn eval(&self, noun: i64, verb: i64) -> Option<i64> {
+        self.constant
+            .checked_add(self.noun.checked_mul(noun)?)?
+            .checked_add(self.verb.checked_mul(verb)?)
+    }
+    /// the noun and verb from 0 to 99 that give `target`, smallest noun
+    /// first.
+    pub fn solve(&self, target: i64) -> Option<(i64, i64)> {
+        (0..100).find_map(|noun| {
+            let rest = target
+                .checked_sub(self.constant)?
+                .checked_sub(self.noun.checked_mul(noun)?)?;
+            let verb = match self.verb {
+                0 if rest == 0 => 0,
+                0 => return None,
+                step if rest.checked_rem(step)? == 0 => rest.checked_div(step)?,
+                _ => return None,
+            };
+            if (0..100).contains(&verb) {
+                Some((noun, verb))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Fit address 0 as a linear function of the noun and verb from three
+/// runs, `None` if a few more runs don't agree with it or the fit
+/// overflows.
+pub fn fit(machine: &Machine) -> Result<Option<Linear>> {
+    let constant = run(machine, 0, 0)?;
+    let (noun, verb) = match (
+        run(machine, 1, 0)?.checked_sub(constant),
+        run(machine, 0, 1)?.checked_sub(constant),
+    ) {
+        (Some(noun), Some(verb)) => (noun, verb),
+        _ => return Ok(None),
+    };
+    let linear = Linear {
+        constant,
+        noun,
+        verb,
+    };
+    for &(noun, verb) in [(1, 1), (99, 99), (37, 58), (64, 3)].iter() {
+        if Some(run(machine, noun, verb)?) != linear.eval(noun, verb) {
+            return Ok(None);
+        }
+    }
+    Ok(Some(linear))
+}
+
+/// The cells of the program whose values end up in `addr` once it halts,
+/// following every value the program moves or computes back to the cells
+/// it started in. Jumps are followed but don't count, nor do the addresses
+/// used to reach a value. It's an error for the program not to halt within
+/// `limit`.
+pub fn dependencies(machine: &Machine, addr: usize, limit: Limit) -> Result<BTreeSet<usize>> {
+    let mut machine = machine.clone();
+    // where the value in each cell came from, the cell itself if it hasn't
+    // been written.
+    let mut sources: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+    let source = |sources: &HashMap<usize, BTreeSet<usize>>, cell: usize| {
+        sources
+            .get(&cell)
+            .cloned()
+            .unwrap_or_else(|| std::iter::once(cell).collect())
+    };
 
-fn run(mem: &mut [usize]) {
-    // instruction pointer
-    let mut ip = 0;
-    loop {
-        match mem[ip] {
-            1 => {
-                let input1 = mem[mem[ip + 1]];
-                let input2 = mem[mem[ip + 2]];
-                mem[mem[ip + 3]] = input1 + input2;
-                ip += 4;
+    let (mut instructions, mut outputs) = (0, 0);
+    while !machine.is_halted() {
+        let reached = match limit {
+            Limit::Outputs(count) => outputs >= count,
+            Limit::Instructions(count) => instructions >= count,
+        };
+        if reached {
+            return Err(Error::no_solution(format!(
+                "the program was still running after {:?}",
+                limit
+            )));
+        }
+        let pc = machine.pc();
+        let instruction = decode_with(|addr| Some(machine.mem_get(addr)), pc)
+            .ok_or_else(|| Error::no_solution(format!("no instruction at {}", pc)))?;
+        let cell = |index: usize| -> Result<usize> {
+            let addr = match instruction.operands[index] {
+                Operand::Position(addr) => addr,
+                Operand::Relative(offset) => machine.sp().checked_add(offset).ok_or_else(|| {
+                    Error::no_solution(format!("relative address overflow at {}", pc))
+                })?,
+                Operand::Immediate(_) => return Ok(pc + 1 + index),
+            };
+            usize::try_from(addr)
+                .map_err(|_| Error::no_solution(format!("negative address at {}", pc)))
+        };
+        match instruction.op {
+            OpcodeType::Add | OpcodeType::Mul | OpcodeType::LessThan | OpcodeType::Equals => {
+                let mut value = source(&sources, cell(0)?);
+                value.extend(source(&sources, cell(1)?));
+                sources.insert(cell(2)?, value);
+            }
+            // input comes from outside the program.
+            OpcodeType::Read => {
+                sources.insert(cell(0)?, BTreeSet::new());
             }
-            2 => {
-                let input1 = mem[mem[ip + 1]];
-                let input2 = mem[mem[ip + 2]];
-                mem[mem[ip + 3]] = input1 * input2;
-                ip += 4;
+            _ => {}
+        }
+        match machine.step(std::iter::empty())? {
+            Some(StopReason::NeedsInput) => {
+                return Err(Error::no_solution("the program wanted input"))
             }
-            99 => return,
-            _ => unreachable!(),
+            Some(StopReason::Output(_)) => outputs += 1,
+            Some(StopReason::Halted) | None => {}
         }
+        instructions += 1;
     }
+    Ok(source(&sources, addr))
 }
 
-pub fn part1(mut mem: Vec<usize>) -> usize {
-    mem[1] = 12;
-    mem[2] = 2;
-    run(&mut mem);
-    mem[0]
+pub fn part1(machine: &Machine) -> Result<i64> {
+    run(machine, 12, 2)
 }
 
-pub fn part2(original_mem: Vec<usize>) -> (usize, usize) {
-    for x in 0..100 {
-        for y in 0..100 {
-            let mut mem = original_mem.clone();
-            mem[1] = x;
-            mem[2] = y;
-            run(&mut mem);
-            if mem[0] == 19_690_720 {
-                return (x, y);
+/// Solve a linear fit where there is one, and only search every noun and
+/// verb when there isn't.
+pub fn part2(machine: &Machine) -> Result<(i64, i64)> {
+    if let Some(linear) = fit(machine)? {
+        if let Some((noun, verb)) = linear.solve(TARGET) {
+            if run(machine, noun, verb)? == TARGET {
+                return Ok((noun, verb));
             }
         }
     }
-    unreachable!()
+    search(machine, TARGET)
 }
 
-pub fn start() {
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer).unwrap();
+pub struct Day02;
+impl Solution for Day02 {
+    type Input<'a> = Machine;
+
+    fn parse(input: &str) -> Result<Machine> {
+        Ok(Machine::from_string(input)?)
+    }
+    fn trace(machine: &mut Machine, tracer: &SharedTracer) {
+        machine.set_tracer(Some(tracer.clone()));
+    }
+    fn part1(machine: &Machine) -> Result<Answer> {
+        Ok(part1(machine)?.into())
+    }
+    fn part2(machine: &Machine) -> Result<Answer> {
+        let (noun, verb) = part2(machine)?;
+        Ok((100 * noun + verb).into())
+    }
+}
 
-    let mem = program_p(&buffer).unwrap().1;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    println!("Program Output: {:?}", part2(mem));
+    #[test]
+    fn example() {
+        let machine = Machine::from_string("1,9,10,3,2,3,11,0,99,30,40,50").unwrap();
+        assert_eq!(run(&machine, 9, 10).unwrap(), 3500);
+        let limit = Limit::Instructions(10);
+        let cells = dependencies(&machine, 0, limit).unwrap();
+        assert_eq!(cells.into_iter().collect::<Vec<_>>(), vec![9, 10, 11]);
+        assert!(dependencies(&machine, 0, Limit::Instructions(1)).is_err());
+
+        // jumps back to itself forever.
+        let machine = Machine::from_string("1105,1,0").unwrap();
+        assert!(dependencies(&machine, 0, limit).is_err());
+    }
+
+    #[test]
+    fn overflow() {
+        let linear = Linear {
+            constant: i64::MAX,
+            noun: 1,
+            verb: 0,
+        };
+        assert_eq!(linear.eval(0, 0), Some(i64::MAX));
+        assert_eq!(linear.eval(1, 0), None);
+        assert_eq!(linear.solve(i64::MIN), None);
+    }
+
+    #[test]
+    fn noun_and_verb() {
+        let buffer = std::fs::read_to_string("./inputs/day02.txt").unwrap();
+        let machine = Machine::from_string(&buffer).unwrap();
+        assert_eq!(part1(&machine).unwrap(), 3_790_645);
+
+        let cells = dependencies(&machine, 0, Limit::Instructions(1000)).unwrap();
+        assert!(cells.contains(&NOUN) && cells.contains(&VERB));
+
+        let linear = fit(&machine).unwrap().unwrap();
+        assert_eq!(linear.solve(TARGET), Some((65, 77)));
+        assert_eq!(part2(&machine).unwrap(), (65, 77));
+        assert_eq!(search(&machine, TARGET).unwrap(), (65, 77));
+    }
 }
@@ -7,6 +7,10 @@ use nom::multi::separated_list;
 use nom::sequence::tuple;
 use nom::IResult;
 
+use crate::error::Result;
+use crate::parsers::complete_nom;
+use crate::solution::{Answer, Solution};
+
 #[derive(Clone, Copy, Debug)]
 enum Direction {
     Up,
@@ -89,6 +93,12 @@ fn wires_p(input: &str) -> IResult<&str, (Vec<WireRel>, Vec<WireRel>)> {
     )(input)
 }
 
+/// The two wires, laid out from the central port.
+pub struct Wires {
+    first: Vec<WireAbs>,
+    second: Vec<WireAbs>,
+}
+
 fn into_wire_abs(wire_rels: &[WireRel]) -> Vec<WireAbs> {
     let (mut x, mut y) = (0, 0);
     let mut result = Vec::new();
@@ -113,14 +123,13 @@ fn dist((x1, y1): (i32, i32), (x2, y2): (i32, i32)) -> i32 {
     (x1 - x2).abs() + (y1 - y2).abs()
 }
 
-pub fn part1(buffer: &str) -> i32 {
-    let (wire1, wire2) = wires_p(&buffer).unwrap().1;
-    let (wire1, wire2) = (into_wire_abs(&wire1), into_wire_abs(&wire2));
+pub fn part1(wires: &Wires) -> i32 {
+    let (wire1, wire2) = (&wires.first, &wires.second);
 
-    let mut min_so_far = i32::max_value();
-    for wire_seg1 in &wire1 {
-        for wire_seg2 in &wire2 {
-            if let Some((x, y)) = wire_seg1.collides(&wire_seg2) {
+    let mut min_so_far = i32::MAX;
+    for wire_seg1 in wire1 {
+        for wire_seg2 in wire2 {
+            if let Some((x, y)) = wire_seg1.collides(wire_seg2) {
                 if x != 0 || y != 0 {
                     min_so_far = min(min_so_far, x.abs() + y.abs())
                 }
@@ -130,21 +139,20 @@ pub fn part1(buffer: &str) -> i32 {
     min_so_far
 }
 
-pub fn part2(buffer: &str) -> i32 {
-    let (wire1, wire2) = wires_p(&buffer).unwrap().1;
-    let (wire1, wire2) = (into_wire_abs(&wire1), into_wire_abs(&wire2));
+pub fn part2(wires: &Wires) -> i32 {
+    let (wire1, wire2) = (&wires.first, &wires.second);
 
-    let mut min_so_far = i32::max_value();
+    let mut min_so_far = i32::MAX;
     let mut wire_1_dist = 0;
 
-    for wire_seg1 in &wire1 {
+    for wire_seg1 in wire1 {
         wire_1_dist += wire_seg1.wire.length;
         let mut wire_2_dist = 0;
 
-        for wire_seg2 in &wire2 {
+        for wire_seg2 in wire2 {
             wire_2_dist += wire_seg2.wire.length;
 
-            if let Some(collision) = wire_seg1.collides(&wire_seg2) {
+            if let Some(collision) = wire_seg1.collides(wire_seg2) {
                 if collision.0 != 0 || collision.1 != 0 {
                     let total_dist = wire_1_dist + wire_2_dist
                         - dist(collision, wire_seg1.end())
@@ -158,56 +166,79 @@ pub fn part2(buffer: &str) -> i32 {
     min_so_far
 }
 
+pub struct Day03;
+impl Solution for Day03 {
+    type Input<'a> = Wires;
+
+    fn parse(input: &str) -> Result<Wires> {
+        let (first, second) = complete_nom(wires_p(input), "two comma separated wires")?;
+        Ok(Wires {
+            first: into_wire_abs(&first),
+            second: into_wire_abs(&second),
+        })
+    }
+    fn part1(wires: &Wires) -> Result<Answer> {
+        Ok(part1(wires).into())
+    }
+    fn part2(wires: &Wires) -> Result<Answer> {
+        Ok(part2(wires).into())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn wires(input: &str) -> Wires {
+        Day03::parse(input).unwrap()
+    }
+
     #[test]
     fn example1() {
-        assert_eq!(part1("R8,U5,L5,D3\nU7,R6,D4,L4"), 6);
+        assert_eq!(part1(&wires("R8,U5,L5,D3\nU7,R6,D4,L4")), 6);
     }
     #[test]
     fn example2() {
         assert_eq!(
-            part1(
+            part1(&wires(
                 "R75,D30,R83,U83,L12,D49,R71,U7,L72\n\
                  U62,R66,U55,R34,D71,R55,D58,R83"
-            ),
+            )),
             159
         );
     }
     #[test]
     fn example3() {
         assert_eq!(
-            part1(
+            part1(&wires(
                 "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\n\
                  U98,R91,D20,R16,D67,R40,U7,R15,U6,R7"
-            ),
+            )),
             135
         );
     }
 
     #[test]
     fn example4() {
-        assert_eq!(part2("R8,U5,L5,D3\nU7,R6,D4,L4"), 30);
+        assert_eq!(part2(&wires("R8,U5,L5,D3\nU7,R6,D4,L4")), 30);
     }
     #[test]
     fn example5() {
         assert_eq!(
-            part2(
+            part2(&wires(
                 "R75,D30,R83,U83,L12,D49,R71,U7,L72\n\
                  U62,R66,U55,R34,D71,R55,D58,R83"
-            ),
+            )),
             610
         );
     }
     #[test]
     fn example6() {
         assert_eq!(
-            part2(
+            part2(&wires(
                 "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\n\
                  U98,R91,D20,R16,D67,R40,U7,R15,U6,R7"
-            ),
+            )),
             410
         );
     }
@@ -1,5 +1,22 @@
 use std::convert::TryFrom;
 
+use crate::error::{Error, Result};
+use crate::parsers::{byte, complete, take_while1_p, ParseResult};
+use crate::solution::{Answer, Solution};
+
+fn number_p(s: &[u8]) -> ParseResult<'_, u32> {
+    let (s, num_str) = take_while1_p(|c| c.is_ascii_digit())(s)?;
+    let number = std::str::from_utf8(num_str).ok()?.parse().ok()?;
+    Some((s, number))
+}
+
+fn range_p(s: &[u8]) -> ParseResult<'_, (u32, u32)> {
+    let (s, lower) = number_p(s)?;
+    let (s, _) = byte(b'-')(s)?;
+    let (s, upper) = number_p(s)?;
+    Some((s, (lower, upper)))
+}
+
 fn digits(mut number: u32) -> [u8; 6] {
     let mut result = [0; 6];
     for digit in result.iter_mut().rev() {
@@ -39,9 +56,9 @@ fn has_pair(number: [u8; 6]) -> bool {
     }
 }
 
-pub fn part1() -> usize {
+pub fn part1((lower, upper): (u32, u32)) -> usize {
     let mut total = 0;
-    for x in 130_254..678_275 {
+    for x in lower..=upper {
         let number = digits(x);
         if has_increasing_digits(number) && has_repeating_digit(number) {
             total += 1;
@@ -50,9 +67,9 @@ pub fn part1() -> usize {
     total
 }
 
-pub fn part2() -> usize {
+pub fn part2((lower, upper): (u32, u32)) -> usize {
     let mut total = 0;
-    for x in 130_254..678_275 {
+    for x in lower..=upper {
         let number = digits(x);
         if has_increasing_digits(number) && has_pair(number) {
             total += 1;
@@ -61,6 +78,28 @@ pub fn part2() -> usize {
     total
 }
 
+pub struct Day04;
+impl Solution for Day04 {
+    type Input<'a> = (u32, u32);
+
+    fn parse(input: &str) -> Result<(u32, u32)> {
+        let (lower, upper) = complete(range_p(input.as_bytes()), "a range of passwords")?;
+        if lower > upper || upper >= 1_000_000 {
+            return Err(Error::parse(format!(
+                "{}-{} isn't a range of six digit passwords",
+                lower, upper
+            )));
+        }
+        Ok((lower, upper))
+    }
+    fn part1(range: &(u32, u32)) -> Result<Answer> {
+        Ok(part1(*range).into())
+    }
+    fn part2(range: &(u32, u32)) -> Result<Answer> {
+        Ok(part2(*range).into())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
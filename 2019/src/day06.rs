@@ -1,6 +1,3 @@
-use std::io;
-use std::io::Read;
-
 use std::collections::HashMap;
 
 use nom::character::complete::{alphanumeric1, char, newline};
@@ -9,6 +6,10 @@ use nom::multi::separated_list;
 use nom::sequence::tuple;
 use nom::IResult;
 
+use crate::error::{Error, Result};
+use crate::parsers::complete_nom;
+use crate::solution::{Answer, Solution};
+
 struct Planet<'str> {
     parent_name: &'str str,
 }
@@ -17,17 +18,20 @@ pub struct PlanetTree<'str> {
     map: HashMap<&'str str, Planet<'str>>,
 }
 impl<'str> PlanetTree<'str> {
-    pub fn from_string(string: &'str str) -> Self {
+    pub fn from_string(string: &'str str) -> Result<Self> {
         let mut map = HashMap::new();
-        for (parent, child) in Self::edges_p(string).unwrap().1 {
+        for (parent, child) in complete_nom(Self::edges_p(string), "one orbit per line")? {
             let planet = Planet {
                 parent_name: parent,
             };
             if map.insert(child, planet).is_some() {
-                panic!("Not a tree");
+                return Err(Error::parse(format!(
+                    "{} orbits more than one planet",
+                    child
+                )));
             }
         }
-        PlanetTree { map }
+        Ok(PlanetTree { map })
     }
     pub fn get_parent(&self, planet_name: &'str str) -> Option<&'str str> {
         self.map.get(planet_name).map(|planet| planet.parent_name)
@@ -114,13 +118,19 @@ pub fn part2(tree: &PlanetTree) -> usize {
     tree.get_distance("YOU", "SAN").saturating_sub(2)
 }
 
-pub fn start() {
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer).unwrap();
-
-    let tree = PlanetTree::from_string(&buffer);
+pub struct Day06;
+impl Solution for Day06 {
+    type Input<'a> = PlanetTree<'a>;
 
-    println!("Program Output: {:?}", part2(&tree));
+    fn parse(input: &str) -> Result<PlanetTree<'_>> {
+        PlanetTree::from_string(input)
+    }
+    fn part1(tree: &PlanetTree<'_>) -> Result<Answer> {
+        Ok(part1(tree).into())
+    }
+    fn part2(tree: &PlanetTree<'_>) -> Result<Answer> {
+        Ok(part2(tree).into())
+    }
 }
 
 #[cfg(test)]
@@ -129,14 +139,16 @@ mod test {
     #[test]
     fn example_orbits() {
         let tree =
-            PlanetTree::from_string("COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L");
+            PlanetTree::from_string("COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L")
+                .unwrap();
         assert_eq!(part1(&tree), 42);
     }
     #[test]
     fn example_distance() {
         let tree = PlanetTree::from_string(
             "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN",
-        );
+        )
+        .unwrap();
         assert_eq!(part2(&tree), 4);
     }
 }
@@ -1,10 +1,11 @@
-use std::io;
-use std::io::Read;
-
 use nom::bytes::complete::take_while_m_n;
 use nom::multi::many0;
 use nom::IResult;
 
+use crate::error::Result;
+use crate::parsers::complete;
+use crate::solution::{Answer, Image, Solution};
+
 const LAYER_WIDTH: usize = 25;
 const LAYER_HEIGHT: usize = 6;
 const LAYER_SIZE: usize = LAYER_HEIGHT * LAYER_WIDTH;
@@ -18,7 +19,7 @@ fn image_p(input: &[u8]) -> IResult<&[u8], Vec<&[u8]>> {
 }
 
 pub fn part1(image: Vec<&[u8]>) -> usize {
-    let mut lowest_zeroes = usize::max_value();
+    let mut lowest_zeroes = usize::MAX;
     let mut two_by_ones = 0;
     for layer in image {
         let num_zeroes = bytecount::count(layer, b'0');
@@ -33,11 +34,7 @@ pub fn part1(image: Vec<&[u8]>) -> usize {
     two_by_ones
 }
 
-const ANSI_RED: &str = "\x1B[41m";
-const ANSI_GREEN: &str = "\x1B[42m";
-const ANSI_RESET: &str = "\x1B[0m";
-
-pub fn part2(image: Vec<&[u8]>) {
+pub fn part2(image: Vec<&[u8]>) -> Image {
     let mut result = [2; LAYER_SIZE];
     image.iter().rev().for_each(|layer| {
         for i in 0..LAYER_SIZE {
@@ -47,23 +44,27 @@ pub fn part2(image: Vec<&[u8]>) {
             result[i] = layer[i]
         }
     });
-    for line in result.chunks(LAYER_WIDTH) {
-        for pixel in line {
-            match pixel {
-                b'1' => print!("{}  {}", ANSI_GREEN, ANSI_RESET),
-                b'0' => print!("  "),
-                _ => print!("{}  {}", ANSI_RED, ANSI_RESET),
-            }
+
+    let mut rendered = Image::new(LAYER_WIDTH, LAYER_HEIGHT);
+    for (y, line) in result.chunks(LAYER_WIDTH).enumerate() {
+        for (x, pixel) in line.iter().enumerate() {
+            rendered.set(x, y, *pixel == b'1');
         }
-        println!();
     }
+    rendered
 }
 
-pub fn start() {
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer).unwrap();
+pub struct Day08;
+impl Solution for Day08 {
+    type Input<'a> = Vec<&'a [u8]>;
 
-    let (_input, image) = image_p(buffer.as_bytes()).unwrap();
-
-    println!("Program Output: {:?}", part2(image));
+    fn parse(input: &str) -> Result<Vec<&[u8]>> {
+        complete(image_p(input.trim().as_bytes()).ok(), "25x6 image layers")
+    }
+    fn part1(image: &Vec<&[u8]>) -> Result<Answer> {
+        Ok(part1(image.clone()).into())
+    }
+    fn part2(image: &Vec<&[u8]>) -> Result<Answer> {
+        Ok(part2(image.clone()).into())
+    }
 }
@@ -1,22 +1,32 @@
-use std::io;
-use std::io::Read;
+use intcode::tracer::SharedTracer;
+use intcode::Machine;
 
-use crate::day05::Machine;
+use crate::day05::last_output;
+use crate::error::Result;
+use crate::solution::{Answer, Solution};
 
-pub fn part1(machine: &mut Machine) -> Vec<i64> {
-    machine.run([1].iter().copied())
+pub fn part1(machine: &mut Machine) -> Result<Vec<i64>> {
+    Ok(machine.run([1].iter().copied())?)
 }
 
-pub fn part2(machine: &mut Machine) -> Vec<i64> {
-    machine.run([2].iter().copied())
+pub fn part2(machine: &mut Machine) -> Result<Vec<i64>> {
+    Ok(machine.run([2].iter().copied())?)
 }
 
-pub fn start() {
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer).unwrap();
+pub struct Day09;
+impl Solution for Day09 {
+    type Input<'a> = Machine;
 
-    let mut machine = Machine::from_string(&buffer);
-
-    println!("Program Output: {:?}", part1(&mut machine));
-    println!("memory size: {}", machine.mem_size());
+    fn parse(input: &str) -> Result<Machine> {
+        Ok(Machine::from_string(input)?)
+    }
+    fn trace(machine: &mut Machine, tracer: &SharedTracer) {
+        machine.set_tracer(Some(tracer.clone()));
+    }
+    fn part1(machine: &Machine) -> Result<Answer> {
+        Ok(last_output(&part1(&mut machine.clone())?)?.into())
+    }
+    fn part2(machine: &Machine) -> Result<Answer> {
+        Ok(last_output(&part2(&mut machine.clone())?)?.into())
+    }
 }
@@ -1,15 +1,15 @@
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
-use std::io;
-use std::io::Read;
 
+use crate::error::{Error, Result};
 use crate::parsers::*;
+use crate::solution::{Answer, Solution};
 
-fn line_p(s: &[u8]) -> ParseResult<&[u8]> {
+fn line_p(s: &[u8]) -> ParseResult<'_, &[u8]> {
     take_while1_p(|c| c == b'#' || c == b'.')(s)
 }
 
-fn arena_p(s: &[u8]) -> ParseResult<Arena> {
+fn arena_p(s: &[u8]) -> ParseResult<'_, Arena<'_>> {
     map(sep_by(line_p, byte(b'\n')), Arena)(s)
 }
 
@@ -28,7 +28,7 @@ fn gcd(a: i32, b: i32) -> i32 {
 
 pub struct Arena<'a>(Vec<&'a [u8]>);
 impl<'a> Arena<'a> {
-    fn iter(&self) -> Iter {
+    fn iter(&self) -> Iter<'_> {
         Iter::new(self.0.as_ref())
     }
 }
@@ -112,25 +112,27 @@ impl Point {
     }
 }
 
-pub fn part1(arena: Arena) {
+pub fn part1(arena: &Arena) -> Result<usize> {
     let mut buffer: HashMap<Point, HashSet<Point>> = HashMap::new();
     for p1 in arena.iter() {
         for p2 in arena.iter() {
             let direction = (p2 - p1).normalize();
-            let asteroid_set = buffer.entry(p1).or_insert_with(HashSet::new);
+            let asteroid_set = buffer.entry(p1).or_default();
             asteroid_set.insert(direction);
         }
     }
 
-    let max_asteroid = buffer
-        .into_iter()
-        .max_by_key(|(_k, v)| v.len())
-        .map(|(k, v)| (k, v.len() - 1, v));
-
-    println!("{:?}", max_asteroid);
+    // every asteroid can see itself, so don't count it.
+    buffer
+        .values()
+        .map(|v| v.len() - 1)
+        .max()
+        .ok_or_else(|| Error::no_solution("there are no asteroids"))
 }
 
-pub fn part2(arena: Arena) {
+/// the 200th asteroid vaporized, which the laser reaches in its first turn
+/// if at least 200 can be seen from the station.
+pub fn part2(arena: &Arena) -> Result<i32> {
     let mut buffer: HashMap<Point, HashMap<Point, Point>> = HashMap::new();
     for p1 in arena.iter() {
         for p2 in arena.iter() {
@@ -139,7 +141,7 @@ pub fn part2(arena: Arena) {
             }
 
             let direction = (p2 - p1).normalize();
-            let asteroid_set = buffer.entry(p1).or_insert_with(HashMap::new);
+            let asteroid_set = buffer.entry(p1).or_default();
 
             let closest = asteroid_set.entry(direction).or_insert(p2);
             if (p2 - p1).len_sq() < (*closest - p1).len_sq() {
@@ -148,19 +150,58 @@ pub fn part2(arena: Arena) {
         }
     }
 
-    let (_max_asteroid, surrounds) = buffer.into_iter().max_by_key(|(_k, v)| v.len()).unwrap();
+    let (_max_asteroid, surrounds) = buffer
+        .into_iter()
+        .max_by_key(|(_k, v)| v.len())
+        .ok_or_else(|| Error::no_solution("there is only one asteroid"))?;
 
     let mut surrounding: Vec<_> = surrounds.into_iter().collect();
     surrounding.sort_by(|(d1, _), (d2, _)| d1.angle().partial_cmp(&d2.angle()).unwrap());
 
-    println!("{:?}", surrounding[200 - 1].1);
+    let (_, vaporized) = surrounding.get(200 - 1).ok_or_else(|| {
+        Error::no_solution(format!(
+            "only {} asteroids can be seen from the station",
+            surrounding.len()
+        ))
+    })?;
+    Ok(vaporized.x * 100 + vaporized.y)
+}
+
+pub struct Day10;
+impl Solution for Day10 {
+    type Input<'a> = Arena<'a>;
+
+    fn parse(input: &str) -> Result<Arena<'_>> {
+        let arena = complete(arena_p(input.as_bytes()), "an asteroid map")?;
+        if arena.iter().next().is_none() {
+            return Err(Error::parse("there are no asteroids on the map"));
+        }
+        Ok(arena)
+    }
+    fn part1(arena: &Arena<'_>) -> Result<Answer> {
+        Ok(part1(arena)?.into())
+    }
+    fn part2(arena: &Arena<'_>) -> Result<Answer> {
+        Ok(part2(arena)?.into())
+    }
 }
 
-pub fn start() {
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer).unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let arena = arena_p(buffer.as_bytes()).unwrap().1;
+    #[test]
+    fn malformed() {
+        assert!(Day10::parse("x").is_err());
+        assert!(Day10::parse("").is_err());
+        assert!(Day10::parse("...\n...").is_err());
+    }
 
-    println!("Program Output: {:?}", part2(arena));
+    #[test]
+    fn example() {
+        let arena = Day10::parse(".#..#\n.....\n#####\n....#\n...##").unwrap();
+        assert_eq!(part1(&arena).unwrap(), 8);
+        // only 8 asteroids are visible, not 200.
+        assert!(part2(&arena).is_err());
+    }
 }
@@ -1,9 +1,11 @@
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
-use std::io;
-use std::io::Read;
 
-use crate::day05::Machine;
+use intcode::tracer::SharedTracer;
+use intcode::{Limit, Machine, StopReason};
+
+use crate::error::{self, Error};
+use crate::solution::{Answer, Image, Solution};
 
 #[must_use]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -14,7 +16,7 @@ pub enum Direction {
     Left,
 }
 impl Direction {
-    fn turn_cw(&mut self) {
+    pub fn turn_cw(&mut self) {
         match self {
             Self::Up => *self = Self::Right,
             Self::Right => *self = Self::Down,
@@ -22,7 +24,7 @@ impl Direction {
             Self::Left => *self = Self::Up,
         }
     }
-    fn turn_ccw(&mut self) {
+    pub fn turn_ccw(&mut self) {
         match self {
             Self::Up => *self = Self::Left,
             Self::Right => *self = Self::Up,
@@ -64,7 +66,7 @@ impl Direction {
     }
 }
 
-#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct Point(pub i32, pub i32);
 impl Point {
     pub fn x(self) -> i32 {
@@ -83,23 +85,11 @@ impl Point {
     }
 }
 
-const ANSI_RED: &str = "\x1B[41m";
-const ANSI_GREEN: &str = "\x1B[42m";
-const ANSI_RESET: &str = "\x1B[0m";
-
 #[derive(Clone, Copy, Debug)]
 enum Colour {
     Black = 0,
     White = 1,
 }
-impl std::fmt::Display for Colour {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Black => write!(f, "{}  {}", ANSI_GREEN, ANSI_RESET),
-            Self::White => write!(f, "{}  {}", ANSI_RED, ANSI_RESET),
-        }
-    }
-}
 impl From<Colour> for i64 {
     fn from(colour: Colour) -> Self {
         colour as i64
@@ -116,6 +106,7 @@ impl TryFrom<i64> for Colour {
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Copy, Debug)]
 enum Rotation {
     CCW = 0,
@@ -144,27 +135,39 @@ struct Robot {
     canvas: HashMap<Point, Colour>,
 }
 impl Robot {
-    pub fn from_string(string: &str) -> Self {
+    pub fn new(cpu: Machine) -> Self {
         Robot {
             position: Point(0, 0),
             direction: Direction::Up,
-            cpu: Machine::from_string(string),
+            cpu,
             canvas: HashMap::new(),
         }
     }
-    pub fn run(&mut self) {
-        while !self.cpu.is_halted() {
-            self.step()
-        }
+    pub fn run(&mut self) -> error::Result<()> {
+        while self.step()? {}
+        Ok(())
     }
-    fn step(&mut self) {
+    /// paint one panel and move, returns false once the robot has halted.
+    fn step(&mut self) -> error::Result<bool> {
         let input = std::iter::once(self.get().into());
-        let output = self.cpu.run(input);
+        let (output, reason) = self.cpu.run_until(input, Limit::Outputs(2))?;
 
-        assert_eq!(output.len(), 2);
-
-        self.paint(output[0].try_into().unwrap());
-        self.advance(output[1].try_into().unwrap());
+        match reason {
+            None => {}
+            Some(StopReason::Halted) if output.is_empty() => return Ok(false),
+            Some(_) => return Err(Error::no_solution("the robot didn't paint and turn")),
+        }
+        match (output[0].try_into(), output[1].try_into()) {
+            (Ok(colour), Ok(rotation)) => {
+                self.paint(colour);
+                self.advance(rotation);
+                Ok(true)
+            }
+            _ => Err(Error::no_solution(format!(
+                "the robot gave invalid instructions {:?}",
+                output
+            ))),
+        }
     }
 
     fn paint(&mut self, colour: Colour) {
@@ -179,29 +182,42 @@ impl Robot {
     }
 }
 
-pub fn part1(cpu: &str) -> usize {
-    let mut robot = Robot::from_string(cpu);
-    robot.run();
-    robot.canvas.len()
+pub fn part1(cpu: &Machine) -> error::Result<usize> {
+    let mut robot = Robot::new(cpu.clone());
+    robot.run()?;
+    Ok(robot.canvas.len())
 }
 
-pub fn part2(cpu: &str) {
-    let mut robot = Robot::from_string(cpu);
+pub fn part2(cpu: &Machine) -> error::Result<Image> {
+    let mut robot = Robot::new(cpu.clone());
     robot.paint(Colour::White);
-    robot.run();
+    robot.run()?;
 
+    let mut image = Image::new(50, 6);
     for y in 0..6 {
         for x in 0..50 {
             let colour = robot.canvas.get(&Point(x, y)).unwrap_or(&Colour::Black);
-            print!("{}", colour)
+            let white = matches!(colour, Colour::White);
+            image.set(x.try_into().unwrap(), y.try_into().unwrap(), white);
         }
-        println!()
     }
+    Ok(image)
 }
 
-pub fn start() {
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer).unwrap();
+pub struct Day11;
+impl Solution for Day11 {
+    type Input<'a> = Machine;
 
-    println!("Program Output: {:?}", part2(&buffer));
+    fn parse(input: &str) -> error::Result<Machine> {
+        Ok(Machine::from_string(input)?)
+    }
+    fn trace(machine: &mut Machine, tracer: &SharedTracer) {
+        machine.set_tracer(Some(tracer.clone()));
+    }
+    fn part1(cpu: &Machine) -> error::Result<Answer> {
+        Ok(part1(cpu)?.into())
+    }
+    fn part2(cpu: &Machine) -> error::Result<Answer> {
+        Ok(part2(cpu)?.into())
+    }
 }
@@ -1,3 +1,31 @@
+use std::convert::TryFrom;
+
+use crate::error::{Error, Result};
+use crate::parsers::{byte, chunk, complete, optional, sep_by, take_while1_p, ParseResult};
+use crate::solution::{Answer, Solution};
+
+fn number_p(s: &[u8]) -> ParseResult<'_, i32> {
+    let (s, sign) = optional(byte(b'-'))(s)?;
+    let (s, num_str) = take_while1_p(|c| c.is_ascii_digit())(s)?;
+    let number: i32 = std::str::from_utf8(num_str).ok()?.parse().ok()?;
+    Some((s, if sign.is_some() { -number } else { number }))
+}
+
+fn position_p(s: &[u8]) -> ParseResult<'_, Point> {
+    let (s, _) = chunk(b"<x=")(s)?;
+    let (s, x) = number_p(s)?;
+    let (s, _) = chunk(b", y=")(s)?;
+    let (s, y) = number_p(s)?;
+    let (s, _) = chunk(b", z=")(s)?;
+    let (s, z) = number_p(s)?;
+    let (s, _) = byte(b'>')(s)?;
+    Some((s, Point(x, y, z)))
+}
+
+fn positions_p(s: &[u8]) -> ParseResult<'_, Vec<Point>> {
+    sep_by(position_p, byte(b'\n'))(s)
+}
+
 fn gcd(mut a: u64, mut b: u64) -> u64 {
     while b != 0 {
         let tmp = b;
@@ -14,7 +42,7 @@ fn lcm(a: u64, b: u64) -> u64 {
 
 #[must_use]
 #[derive(Copy, Clone, Debug)]
-struct Point(i32, i32, i32);
+pub struct Point(i32, i32, i32);
 impl std::ops::Add for Point {
     type Output = Point;
     fn add(self, other: Point) -> Point {
@@ -119,13 +147,11 @@ impl PlanetDGroup {
     }
 }
 
-pub fn part1() -> i32 {
-    let mut planets = [
-        Planet::new(1, -4, 3),
-        Planet::new(-14, 9, -4),
-        Planet::new(-4, -6, 7),
-        Planet::new(6, -9, -11),
-    ];
+pub fn part1(positions: &[Point]) -> i32 {
+    let mut planets: Vec<_> = positions
+        .iter()
+        .map(|Point(x, y, z)| Planet::new(*x, *y, *z))
+        .collect();
 
     for _ in 0..1000 {
         let accelerations: Vec<_> = planets
@@ -147,20 +173,29 @@ pub fn part1() -> i32 {
 // <x=4, y=-8, z=8>
 // <x=3, y=5, z=-1>
 
-// puzzle input
-// <x=1, y=-4, z=3>
-// <x=-14, y=9, z=-4>
-// <x=-4, y=-6, z=7>
-// <x=6, y=-9, z=-11>
-
-pub fn part2() -> u64 {
-    let cycle_x = PlanetDGroup::new(1, -14, -4, 6).cycle_len();
-    let cycle_y = PlanetDGroup::new(-4, 9, -6, -9).cycle_len();
-    let cycle_z = PlanetDGroup::new(3, -4, 7, -11).cycle_len();
+pub fn part2(positions: &[Point; 4]) -> u64 {
+    let [a, b, c, d] = positions;
+    let cycle_x = PlanetDGroup::new(a.0, b.0, c.0, d.0).cycle_len();
+    let cycle_y = PlanetDGroup::new(a.1, b.1, c.1, d.1).cycle_len();
+    let cycle_z = PlanetDGroup::new(a.2, b.2, c.2, d.2).cycle_len();
 
     lcm(cycle_x, lcm(cycle_y, cycle_z))
 }
 
-pub fn start() {
-    println!("Program Output: {:?}", part2());
+pub struct Day12;
+impl Solution for Day12 {
+    type Input<'a> = [Point; 4];
+
+    fn parse(input: &str) -> Result<[Point; 4]> {
+        let positions = complete(positions_p(input.as_bytes()), "one moon position per line")?;
+        <[Point; 4]>::try_from(positions).map_err(|positions| {
+            Error::parse(format!("expected four moons, got {}", positions.len()))
+        })
+    }
+    fn part1(positions: &[Point; 4]) -> Result<Answer> {
+        Ok(part1(positions).into())
+    }
+    fn part2(positions: &[Point; 4]) -> Result<Answer> {
+        Ok(part2(positions).into())
+    }
 }
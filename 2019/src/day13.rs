@@ -1,10 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::convert::TryFrom;
 
-use ncurses as nc;
-use ncurses::constants as ncc;
+use intcode::tracer::SharedTracer;
+use intcode::Machine;
 
-use crate::day05::Machine;
+use crate::display::{Curses, Display, InputSource, Key, Keyboard};
+use crate::error::{self, Error};
+use crate::saves::{Metadata, SaveStore};
+use crate::solution::{Answer, Solution};
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 enum Pixel {
@@ -39,86 +42,269 @@ impl TryFrom<i64> for Pixel {
     }
 }
 
+fn pixel(data: i64) -> error::Result<Pixel> {
+    Pixel::try_from(data).map_err(|_| Error::no_solution(format!("unknown tile {}", data)))
+}
+
+#[derive(Clone, Default)]
 struct Screen {
     buffer: HashMap<(i64, i64), Pixel>,
+    score: i64,
+    ball: Option<(i64, i64)>,
+    paddle: Option<(i64, i64)>,
 }
 impl Screen {
-    fn new(output: Vec<i64>) -> Self {
-        let mut buffer = HashMap::new();
+    fn new(output: Vec<i64>) -> error::Result<Self> {
+        let mut screen = Screen::default();
+        screen.update(&output)?;
+        Ok(screen)
+    }
+    fn update(&mut self, output: &[i64]) -> error::Result<()> {
         for entry in output.chunks_exact(3) {
             match entry {
-                [x, y, data] => buffer.insert((*x, *y), Pixel::try_from(*data).unwrap()),
+                [-1, 0, score] => self.score = *score,
+                [x, y, data] => {
+                    let pixel = pixel(*data)?;
+                    match pixel {
+                        Pixel::Ball => self.ball = Some((*x, *y)),
+                        Pixel::Paddle => self.paddle = Some((*x, *y)),
+                        _ => {}
+                    }
+                    self.buffer.insert((*x, *y), pixel);
+                }
                 _ => unreachable!(),
             };
         }
-        Screen { buffer }
+        Ok(())
     }
     fn block_count(&self) -> usize {
         self.buffer.values().filter(|v| **v == Pixel::Block).count()
     }
+    fn draw<D: Display + ?Sized>(&self, display: &mut D) {
+        display.clear();
+        for (&(x, y), &pixel) in self.buffer.iter() {
+            let x = i32::try_from(x).unwrap();
+            let y = i32::try_from(y).unwrap();
+            display.put(x, y, pixel.into());
+        }
+        display.print(0, SCORE_ROW, &format!("Score: {}", self.score));
+        display.refresh();
+    }
 }
 
-pub fn part1(mut machine: Machine) -> usize {
-    let output = machine.run(std::iter::empty());
-    let screen = Screen::new(output);
-    screen.block_count()
+pub fn part1(mut machine: Machine) -> error::Result<usize> {
+    let output = machine.run(std::iter::empty())?;
+    let screen = Screen::new(output)?;
+    Ok(screen.block_count())
 }
 
-const KEY_Q: i32 = 'q' as i32;
-const KEY_SPACE: i32 = ' ' as i32;
-const KEY_S: i32 = 's' as i32;
-const KEY_R: i32 = 'r' as i32;
-
-const DAY_13_MACHINE: u32 = 13;
+/// the line under the board.
+const SCORE_ROW: i32 = 26;
+const SAVE_SLOT: &str = "day13";
+/// where the game was saved before there were named slots, restored until
+/// the game is saved again.
+const LEGACY_SLOT: &str = "save_13";
+/// moves the game can be rewound by.
+const HISTORY: usize = 10_000;
 
-pub fn part2(mut machine: Machine) {
+/// Play the game without a screen, keeping the paddle under the ball until
+/// every block is broken. Returns the final score.
+pub fn part2(mut machine: Machine) -> error::Result<i64> {
     machine.mem_set(0, 2);
+    let mut screen = Screen::default();
+    let mut output = machine.run(std::iter::empty())?;
+    loop {
+        screen.update(&output)?;
+        if machine.is_halted() {
+            break;
+        }
+        let joystick = match (screen.ball, screen.paddle) {
+            (Some((ball, _)), Some((paddle, _))) => (ball - paddle).signum(),
+            _ => return Err(Error::no_solution("no ball or paddle on the screen")),
+        };
+        output = machine.run(std::iter::once(joystick))?;
+    }
 
-    nc::initscr();
-    nc::cbreak();
-    nc::keypad(nc::stdscr(), true);
-    nc::noecho();
+    match screen.block_count() {
+        0 => Ok(screen.score),
+        blocks => Err(Error::no_solution(format!(
+            "the ball got past the paddle with {} blocks left",
+            blocks
+        ))),
+    }
+}
 
-    loop {
-        let input = nc::getch();
-        let mut joystick = 0;
-
-        match input {
-            ncc::KEY_LEFT => joystick = -1,
-            ncc::KEY_RIGHT => joystick = 1,
-            KEY_SPACE => joystick = 0,
-            KEY_Q => break,
-            KEY_S => machine.save(DAY_13_MACHINE),
-            KEY_R => machine = Machine::restore(DAY_13_MACHINE),
+/// The whole screen for a game in `machine`. Only the machine is saved, not
+/// the screen, so the game's start-up code is run again on a copy of its
+/// memory to draw every tile.
+fn board(machine: &Machine) -> error::Result<Screen> {
+    Screen::new(Machine::new(machine.memory()).run(std::iter::empty())?)
+}
+
+/// play the game by hand in the terminal, returning the last score shown.
+pub fn play_by_hand(machine: Machine, store: &SaveStore) -> error::Result<i64> {
+    let mut curses = Curses::init();
+    play(machine, store, &mut curses, &mut Keyboard)
+}
+
+/// Play the game on `display` with keys from `keys` until they run out or
+/// `q` is pressed, returning the last score shown. The arrows move the
+/// joystick and space leaves it where it is, `s` saves the game to `store`
+/// and `r` restores it without moving, `b` steps back one move.
+pub fn play<D, I>(
+    mut machine: Machine,
+    store: &SaveStore,
+    display: &mut D,
+    keys: &mut I,
+) -> error::Result<i64>
+where
+    D: Display + ?Sized,
+    I: InputSource + ?Sized,
+{
+    let program = machine.clone();
+    machine.mem_set(0, 2);
+    machine.set_history(HISTORY);
+    // the screen before each move the machine can be rewound by.
+    let mut screens = VecDeque::new();
+
+    let mut screen = Screen::new(machine.run(std::iter::empty())?)?;
+    screen.draw(display);
+    while let Some(key) = keys.key() {
+        let joystick = match key {
+            Key::Left => -1,
+            Key::Right => 1,
+            Key::Char(' ') => 0,
+            Key::Char('q') => break,
+            Key::Char('b') => {
+                if machine.rewind(1) == 1 {
+                    if let Some(previous) = screens.pop_back() {
+                        screen = previous;
+                        screen.draw(display);
+                    }
+                }
+                continue;
+            }
+            Key::Char('s') => {
+                let metadata = Metadata {
+                    day: Some(13),
+                    score: Some(screen.score),
+                    ..Metadata::default()
+                };
+                store.save(SAVE_SLOT, &program, &machine, metadata)?;
+                0
+            }
+            Key::Char('r') => {
+                let slot = if store.exists(SAVE_SLOT) {
+                    SAVE_SLOT
+                } else {
+                    LEGACY_SLOT
+                };
+                machine = store.load(slot, &program)?;
+                machine.set_history(HISTORY);
+                screens.clear();
+                screen = board(&machine)?;
+                screen.draw(display);
+                continue;
+            }
             _ => continue,
         };
 
-        let output = machine.run(std::iter::once(joystick));
+        if screens.len() == HISTORY {
+            screens.pop_front();
+        }
+        screens.push_back(screen.clone());
+        let output = machine.run(std::iter::once(joystick))?;
+        screen.update(&output)?;
 
         for entry in output.chunks_exact(3) {
             match entry {
-                [-1, 0, score] => {
-                    nc::mvprintw(26, 0, format!("Score: {}", score).as_str());
-                }
+                [-1, 0, score] => display.print(0, SCORE_ROW, &format!("Score: {}", score)),
                 [x, y, data] => {
-                    let c: char = Pixel::try_from(*data).unwrap().into();
+                    let c: char = pixel(*data)?.into();
                     let x = i32::try_from(*x).unwrap();
                     let y = i32::try_from(*y).unwrap();
-                    nc::mvaddch(y, x, c.into());
+                    display.put(x, y, c);
                 }
                 _ => unreachable!(),
             };
         }
-        nc::refresh();
+        display.refresh();
     }
-    nc::endwin();
+    Ok(screen.score)
 }
 
-pub fn start() {
-    let buffer = std::fs::read_to_string("./inputs/day13.txt")
-        .expect("Something went wrong reading the file");
+pub struct Day13;
+impl Solution for Day13 {
+    type Input<'a> = Machine;
+
+    fn parse(input: &str) -> error::Result<Machine> {
+        Ok(Machine::from_string(input)?)
+    }
+    fn trace(machine: &mut Machine, tracer: &SharedTracer) {
+        machine.set_tracer(Some(tracer.clone()));
+    }
+    fn part1(machine: &Machine) -> error::Result<Answer> {
+        Ok(part1(machine.clone())?.into())
+    }
+    fn part2(machine: &Machine) -> error::Result<Answer> {
+        Ok(part2(machine.clone())?.into())
+    }
+}
 
-    let machine = Machine::from_string(&buffer);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::{Framebuffer, Script};
 
-    println!("Program Output: {:?}", part2(machine));
+    #[test]
+    fn autopilot() {
+        let buffer = std::fs::read_to_string("./inputs/day13.txt").unwrap();
+        let machine = Machine::from_string(&buffer).unwrap();
+        assert_eq!(part1(machine.clone()).unwrap(), 380);
+        assert_eq!(part2(machine).unwrap(), 18647);
+    }
+
+    #[test]
+    fn scripted_game() {
+        let buffer = std::fs::read_to_string("./inputs/day13.txt").unwrap();
+        let machine = Machine::from_string(&buffer).unwrap();
+
+        let store = SaveStore::new(std::env::temp_dir().join("advent-of-code-day13-unused"));
+        let mut start = Framebuffer::new();
+        play(machine.clone(), &store, &mut start, &mut Script::default()).unwrap();
+        assert!(start.snapshot().ends_with("Score: 0"));
+
+        // moving and stepping back leaves the board as it started.
+        let mut screen = Framebuffer::new();
+        let mut keys = Script::parse("left left space b b b q right").unwrap();
+        assert_eq!(play(machine, &store, &mut screen, &mut keys).unwrap(), 0);
+        assert_eq!(keys.len(), 1);
+        assert_eq!(screen.snapshot(), start.snapshot());
+    }
+
+    #[test]
+    fn restore_legacy_save() {
+        let buffer = std::fs::read_to_string("./inputs/day13.txt").unwrap();
+        let machine = Machine::from_string(&buffer).unwrap();
+        let name = format!("advent-of-code-day13-{}", std::process::id());
+        let dir = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy("./saves/save_13.json", dir.join("save_13.json")).unwrap();
+
+        let store = SaveStore::new(&dir);
+        let mut screen = Framebuffer::new();
+        let mut keys = Script::parse("r q").unwrap();
+        let score = play(machine, &store, &mut screen, &mut keys).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(keys.is_empty());
+
+        // the board as it was saved, near the end with every block broken.
+        assert_eq!(score, 18647);
+        assert_eq!(screen.get(12, 18), 'o');
+        assert_eq!(screen.get(11, 24), '_');
+        let snapshot = screen.snapshot();
+        assert!(snapshot.ends_with("Score: 18647"));
+        assert_eq!(snapshot.lines().next(), Some("#".repeat(42).as_str()));
+        assert!(!snapshot.contains('x'));
+    }
 }
@@ -1,56 +1,57 @@
+use crate::error::{Error, Result};
 use crate::parsers::*;
+use crate::solution::{Answer, Solution};
 use std::collections::{HashMap, HashSet};
 
-fn ws(s: &[u8]) -> ParseResult<()> {
+fn ws(s: &[u8]) -> ParseResult<'_, ()> {
     void(take_while_p(|c| c == b' ' || c == b'\t'))(s)
 }
 
-fn newline(s: &[u8]) -> ParseResult<()> {
+fn newline(s: &[u8]) -> ParseResult<'_, ()> {
     void(satisfy(|c| c == b'\n'))(s)
 }
 
-fn number(s: &[u8]) -> ParseResult<u64> {
+fn number(s: &[u8]) -> ParseResult<'_, u64> {
     let (s, num_bytes) = take_while1_p(|c| c.is_ascii_digit())(s)?;
     let (s, _) = ws(s)?;
 
-    let num_str = std::str::from_utf8(num_bytes).unwrap();
-    let number = num_str.parse().unwrap();
+    let number = std::str::from_utf8(num_bytes).ok()?.parse().ok()?;
     Some((s, number))
 }
 
-fn identifier(s: &[u8]) -> ParseResult<&[u8]> {
+fn identifier(s: &[u8]) -> ParseResult<'_, &[u8]> {
     let (s, result) = take_while1_p(|c| c.is_ascii_alphabetic())(s)?;
     let (s, _) = ws(s)?;
     Some((s, result))
 }
 
-fn arrow(s: &[u8]) -> ParseResult<()> {
+fn arrow(s: &[u8]) -> ParseResult<'_, ()> {
     let (s, _) = void(chunk(b"=>"))(s)?;
     let (s, _) = ws(s)?;
     Some((s, ()))
 }
 
-fn comma(s: &[u8]) -> ParseResult<()> {
+fn comma(s: &[u8]) -> ParseResult<'_, ()> {
     let (s, _) = void(satisfy(|c| c == b','))(s)?;
     let (s, _) = ws(s)?;
     Some((s, ()))
 }
 
-fn quantity(s: &[u8]) -> ParseResult<Quantity> {
+fn quantity(s: &[u8]) -> ParseResult<'_, Quantity<'_>> {
     let (s, num) = number(s)?;
     let (s, id) = identifier(s)?;
-    let id_str = std::str::from_utf8(id).unwrap();
+    let id_str = std::str::from_utf8(id).ok()?;
     Some((s, Quantity::new(num, id_str)))
 }
 
-fn rule(s: &[u8]) -> ParseResult<Rule> {
+fn rule(s: &[u8]) -> ParseResult<'_, Rule<'_>> {
     let (s, quantities) = sep_by(quantity, comma)(s)?;
     let (s, _) = arrow(s)?;
     let (s, result) = quantity(s)?;
     Some((s, Rule::new(result, quantities)))
 }
 
-fn rules(s: &[u8]) -> ParseResult<Vec<Rule>> {
+fn rules(s: &[u8]) -> ParseResult<'_, Vec<Rule<'_>>> {
     sep_by(rule, newline)(s)
 }
 
@@ -83,6 +84,7 @@ impl<'a> Rule<'a> {
 
 const FUEL: &str = "FUEL";
 const ORE: &str = "ORE";
+#[derive(Default)]
 struct Reagent<'a> {
     inputs: Vec<Quantity<'a>>,
     quantity: Option<u64>,
@@ -104,15 +106,6 @@ impl<'a> Reagent<'a> {
             .all(|output| processed_reagents.contains(output))
     }
 }
-impl<'a> Default for Reagent<'a> {
-    fn default() -> Self {
-        Reagent {
-            inputs: Vec::new(),
-            quantity: None,
-            outputs: Vec::new(),
-        }
-    }
-}
 
 pub struct ReagentGraph<'a> {
     map: HashMap<&'a str, Reagent<'a>>,
@@ -140,6 +133,17 @@ impl<'a> ReagentGraph<'a> {
         current.inputs = rule.inputs;
     }
 
+    /// The first reagent that's needed but never made, if there is one.
+    fn missing_reagent(&self) -> Option<&'a str> {
+        if !self.map.contains_key(FUEL) {
+            return Some(FUEL);
+        }
+        self.map
+            .iter()
+            .find(|(&name, reagent)| name != ORE && reagent.quantity.is_none())
+            .map(|(&name, _)| name)
+    }
+
     fn get_mut_reagent(&mut self, name: &'a str) -> &mut Reagent<'a> {
         self.map.entry(name).or_default()
     }
@@ -181,19 +185,13 @@ impl<'a> ReagentGraph<'a> {
     }
 }
 
-pub fn part1(input: &[u8]) -> u64 {
-    let rules = rules(input).unwrap().1;
-    let graph = ReagentGraph::from_rules(rules);
-
+pub fn part1(graph: &ReagentGraph<'_>) -> u64 {
     graph.get_ore(1)
 }
 
 const TRILLION: u64 = 1_000_000_000_000;
-pub fn part2(input: &[u8]) -> u64 {
-    let rules = rules(input).unwrap().1;
-    let graph = ReagentGraph::from_rules(rules);
-
-    let mut lower = 0;
+pub fn part2(graph: &ReagentGraph<'_>) -> u64 {
+    let mut lower: u64 = 0;
     let mut upper = 10_000_000_000;
     while lower < upper {
         let middle = (lower + upper + 1) / 2;
@@ -208,18 +206,31 @@ pub fn part2(input: &[u8]) -> u64 {
     lower
 }
 
-pub fn start() {
-    let buffer = std::fs::read_to_string("./inputs/day14.txt")
-        .expect("Something went wrong reading the file");
+pub struct Day14;
+impl Solution for Day14 {
+    type Input<'a> = ReagentGraph<'a>;
 
-    println!("Program Output: {:?}", part2(buffer.as_bytes()));
+    fn parse(input: &str) -> Result<ReagentGraph<'_>> {
+        let rules = complete(rules(input.as_bytes()), "one reaction per line")?;
+        let graph = ReagentGraph::from_rules(rules);
+        if let Some(name) = graph.missing_reagent() {
+            return Err(Error::parse(format!("no reaction makes {}", name)));
+        }
+        Ok(graph)
+    }
+    fn part1(graph: &ReagentGraph<'_>) -> Result<Answer> {
+        Ok(part1(graph).into())
+    }
+    fn part2(graph: &ReagentGraph<'_>) -> Result<Answer> {
+        Ok(part2(graph).into())
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
-    const INPUT1: &[u8] = b"\
+    const INPUT1: &str = "\
     9 ORE => 2 A\n\
     8 ORE => 3 B\n\
     7 ORE => 5 C\n\
@@ -230,6 +241,6 @@ mod test {
 
     #[test]
     fn example_1() {
-        assert_eq!(part1(INPUT1), 165);
+        assert_eq!(part1(&Day14::parse(INPUT1).unwrap()), 165);
     }
 }
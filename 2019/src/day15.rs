@@ -1,11 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::convert::{TryFrom, TryInto};
 
-use ncurses as nc;
+use intcode::tracer::SharedTracer;
+use intcode::{Machine, StopReason};
 
-use crate::day05::Machine;
 use crate::day11::{Direction, Point};
+use crate::display::{Curses, Display, InputSource, Key, Keyboard};
+use crate::error::{self, Error};
+use crate::solution::{Answer, Solution};
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum Cell {
     Empty,
     Wall,
@@ -23,112 +27,334 @@ impl TryFrom<i64> for Cell {
     }
 }
 
-fn test_direction(machine: &mut Machine, position: Point, direction: Direction) {
-    let mut new_pos = position;
-    new_pos.step(direction);
-
+/// move the droid one step, returning what it found.
+fn move_droid(machine: &mut Machine, direction: Direction) -> error::Result<Cell> {
     let input = std::iter::once(direction.to_joystick_day_11());
-    let opposite = std::iter::once(direction.opposite().to_joystick_day_11());
-    match machine.run(input)[0].try_into().unwrap() {
-        Cell::Wall => {
-            nc::mvaddch(new_pos.y(), new_pos.x(), '#'.into());
+    match machine.resume(input)? {
+        StopReason::Output(status) => status
+            .try_into()
+            .map_err(|()| Error::no_solution(format!("unknown status code {}", status))),
+        _ => Err(Error::no_solution("the droid didn't report its status")),
+    }
+}
+
+/// look for a wall next to the droid, leaving it where it was.
+fn test_direction(machine: &mut Machine, direction: Direction) -> error::Result<Cell> {
+    let cell = move_droid(machine, direction)?;
+    if cell != Cell::Wall {
+        move_droid(machine, direction.opposite())?;
+    }
+    Ok(cell)
+}
+
+fn next_to(mut position: Point, direction: Direction) -> Point {
+    position.step(direction);
+    position
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Left,
+    Direction::Down,
+    Direction::Right,
+];
+
+/// The open cells of the ship, as mapped by the droid from `Point(0, 0)`.
+#[derive(Debug, Default)]
+pub struct Ship {
+    open: HashSet<Point>,
+    oxygen: Option<Point>,
+}
+impl Ship {
+    /// fewest moves from `from` to every open cell.
+    fn distances(&self, from: Point) -> HashMap<Point, u32> {
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::new();
+        distances.insert(from, 0);
+        queue.push_back(from);
+        while let Some(position) = queue.pop_front() {
+            let distance = distances[&position];
+            for &direction in DIRECTIONS.iter() {
+                let next = next_to(position, direction);
+                if self.open.contains(&next) && !distances.contains_key(&next) {
+                    distances.insert(next, distance + 1);
+                    queue.push_back(next);
+                }
+            }
         }
-        Cell::Empty => {
-            machine.run(opposite);
+        distances
+    }
+    /// the fewest moves from the start to the oxygen system.
+    pub fn oxygen_distance(&self) -> Option<u32> {
+        let oxygen = self.oxygen?;
+        self.distances(Point(0, 0)).get(&oxygen).copied()
+    }
+    /// minutes for the oxygen to fill the ship, one cell a minute.
+    pub fn fill_time(&self) -> Option<u32> {
+        let oxygen = self.oxygen?;
+        self.distances(oxygen).values().max().copied()
+    }
+}
+
+/// Map the whole ship with a depth first search, testing the unknown cells
+/// around each new position and backing the droid out of dead ends.
+pub fn map_ship(machine: &mut Machine) -> error::Result<Ship> {
+    let mut ship = Ship::default();
+    let mut walls = HashSet::new();
+    let mut position = Point(0, 0);
+    let mut visited = HashSet::new();
+    // the moves from the start to the droid.
+    let mut path: Vec<Direction> = Vec::new();
+    ship.open.insert(position);
+    visited.insert(position);
+
+    loop {
+        for &direction in DIRECTIONS.iter() {
+            let next = next_to(position, direction);
+            if ship.open.contains(&next) || walls.contains(&next) {
+                continue;
+            }
+            match test_direction(machine, direction)? {
+                Cell::Wall => {
+                    walls.insert(next);
+                }
+                Cell::Empty => {
+                    ship.open.insert(next);
+                }
+                Cell::Cylinder => {
+                    ship.open.insert(next);
+                    ship.oxygen = Some(next);
+                }
+            }
         }
-        Cell::Cylinder => {
-            machine.run(opposite);
+
+        let unvisited = DIRECTIONS.iter().copied().find(|&direction| {
+            let next = next_to(position, direction);
+            ship.open.contains(&next) && !visited.contains(&next)
+        });
+        let direction = match unvisited {
+            Some(direction) => {
+                path.push(direction);
+                direction
+            }
+            None => match path.pop() {
+                Some(direction) => direction.opposite(),
+                None => break,
+            },
+        };
+        if move_droid(machine, direction)? == Cell::Wall {
+            return Err(Error::no_solution("the droid hit a wall it had been past"));
         }
+        position.step(direction);
+        visited.insert(position);
     }
+    Ok(ship)
+}
+
+/// The distance from the start to the oxygen system.
+pub fn part1(ship: &Ship) -> error::Result<u32> {
+    ship.oxygen_distance()
+        .ok_or_else(|| Error::no_solution("oxygen system not found"))
+}
+
+/// The minutes it takes to fill the ship with oxygen.
+pub fn part2(ship: &Ship) -> error::Result<u32> {
+    ship.fill_time()
+        .ok_or_else(|| Error::no_solution("oxygen system not found"))
 }
 
-const KEY_Q: i32 = 'q' as i32;
-pub fn part1(mut machine: Machine) {
-    nc::initscr();
-    nc::cbreak();
-    nc::keypad(nc::stdscr(), true);
-    nc::noecho();
+/// moves the droid can be rewound by.
+const HISTORY: usize = 10_000;
+
+/// Where the droid was before a step, and how many inputs the step took.
+struct Step {
+    inputs: usize,
+    position: Point,
+    distance: u32,
+    oxygen_distance: Option<u32>,
+}
+
+/// explore the ship by hand, `b` takes the droid back a step. Returns the
+/// distance from the start to the oxygen system, if it was found, and the
+/// furthest distance from the oxygen system seen.
+pub fn explore_by_hand(mut machine: Machine) -> error::Result<(Option<u32>, u32)> {
+    let mut curses = Curses::init();
+    explore(&mut machine, &mut curses, &mut Keyboard)
+}
 
+/// explore the ship on `display` with keys from `keys`, until they run out
+/// or `q` is pressed.
+pub fn explore<D, I>(
+    machine: &mut Machine,
+    display: &mut D,
+    keys: &mut I,
+) -> error::Result<(Option<u32>, u32)>
+where
+    D: Display + ?Sized,
+    I: InputSource + ?Sized,
+{
     let mut distances = HashMap::new();
     let mut current_distance = 0;
 
     let mut oxygen_distances = HashMap::new();
     let mut oxygen_distance = None;
+    let mut oxygen_position = None;
     let mut max_oxygen_distance = 0;
 
     let mut position = Point(25, 25);
 
     distances.insert(position, 0);
+    machine.set_history(HISTORY);
+    let mut steps: Vec<Step> = Vec::new();
 
-    loop {
-        let direction = match nc::getch() {
-            nc::KEY_UP => Direction::Up,
-            nc::KEY_LEFT => Direction::Left,
-            nc::KEY_RIGHT => Direction::Right,
-            nc::KEY_DOWN => Direction::Down,
-            KEY_Q => break,
+    while let Some(key) = keys.key() {
+        let direction = match key {
+            Key::Up => Direction::Up,
+            Key::Left => Direction::Left,
+            Key::Right => Direction::Right,
+            Key::Down => Direction::Down,
+            Key::Char('b') => {
+                // the map stays explored, only the droid goes back.
+                match steps.pop() {
+                    Some(step) if machine.history_len() >= step.inputs => {
+                        machine.rewind(step.inputs);
+                        let trail = if oxygen_distance.is_some() { 'x' } else { '.' };
+                        display.put(position.x(), position.y(), trail);
+                        position = step.position;
+                        current_distance = step.distance;
+                        oxygen_distance = step.oxygen_distance;
+                        display.put(position.x(), position.y(), '@');
+                        display.refresh();
+                    }
+                    _ => steps.clear(),
+                }
+                continue;
+            }
+            Key::Char('q') => break,
             _ => continue,
         };
+        let mut step = Step {
+            inputs: 1,
+            position,
+            distance: current_distance,
+            oxygen_distance,
+        };
 
         if oxygen_distance.is_some() {
-            nc::mvaddch(position.y(), position.x(), 'x'.into());
+            display.put(position.x(), position.y(), 'x');
         } else {
-            nc::mvaddch(position.y(), position.x(), '.'.into());
+            display.put(position.x(), position.y(), '.');
         }
 
-        let input = std::iter::once(direction.to_joystick_day_11());
-        match machine.run(input)[0].try_into().unwrap() {
+        match move_droid(machine, direction)? {
             Cell::Wall => {
                 let mut new_point = position;
                 new_point.step(direction);
-                nc::mvaddch(new_point.y(), new_point.x(), '#'.into());
+                display.put(new_point.x(), new_point.y(), '#');
             }
             Cell::Empty => {
                 position.step(direction);
             }
             Cell::Cylinder => {
                 position.step(direction);
+                oxygen_position = Some(position);
                 oxygen_distances.insert(position, 0);
                 oxygen_distance = Some(0);
             }
         }
 
-        test_direction(&mut machine, position, Direction::Up);
-        test_direction(&mut machine, position, Direction::Left);
-        test_direction(&mut machine, position, Direction::Down);
-        test_direction(&mut machine, position, Direction::Right);
+        for &direction in DIRECTIONS.iter() {
+            step.inputs += match test_direction(machine, direction)? {
+                Cell::Wall => {
+                    let wall = next_to(position, direction);
+                    display.put(wall.x(), wall.y(), '#');
+                    1
+                }
+                Cell::Empty | Cell::Cylinder => 2,
+            };
+        }
+        steps.push(step);
 
-        nc::mvaddch(position.y(), position.x(), '@'.into());
+        display.put(position.x(), position.y(), '@');
 
         current_distance = *distances.entry(position).or_insert(current_distance + 1);
         oxygen_distance = oxygen_distance.map(|old_distance| {
             let distance = oxygen_distances.entry(position).or_insert(old_distance + 1);
             max_oxygen_distance = max_oxygen_distance.max(*distance);
-            nc::mvprintw(
-                3,
-                1,
-                format!("Current Oxygen Distance: {}", distance).as_str(),
-            );
+            display.print(1, 3, &format!("Current Oxygen Distance: {}", distance));
             *distance
         });
 
-        nc::mvprintw(1, 1, format!("Distance: {}", current_distance).as_str());
-        nc::mvprintw(
-            2,
+        display.print(1, 1, &format!("Distance: {}", current_distance));
+        display.print(
             1,
-            format!("Max Oxygen Distance: {}", max_oxygen_distance).as_str(),
+            2,
+            &format!("Max Oxygen Distance: {}", max_oxygen_distance),
         );
 
-        nc::refresh();
+        display.refresh();
     }
-    nc::endwin();
+
+    let start_distance = oxygen_position.map(|position| distances[&position]);
+    Ok((start_distance, max_oxygen_distance))
 }
 
-pub fn start() {
-    let buffer = std::fs::read_to_string("./inputs/day15.txt")
-        .expect("Something went wrong reading the file");
+pub struct Day15;
+impl Solution for Day15 {
+    /// both parts only need the map, so the droid explores once, up front.
+    type Input<'a> = Ship;
+
+    fn parse(input: &str) -> error::Result<Ship> {
+        map_ship(&mut Machine::from_string(input)?)
+    }
+    /// the droid explores while parsing, so it's traced from the start.
+    fn parse_traced(input: &str, tracer: &SharedTracer) -> error::Result<Ship> {
+        map_ship(&mut Machine::from_string(input)?.with_tracer(tracer.clone()))
+    }
+    fn part1(ship: &Ship) -> error::Result<Answer> {
+        Ok(part1(ship)?.into())
+    }
+    fn part2(ship: &Ship) -> error::Result<Answer> {
+        Ok(part2(ship)?.into())
+    }
+}
 
-    let machine = Machine::from_string(&buffer);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::{Framebuffer, Script};
 
-    println!("Program Output: {:?}", part1(machine));
+    #[test]
+    fn automatic_exploration() {
+        let buffer = std::fs::read_to_string("./inputs/day15.txt").unwrap();
+        let ship = map_ship(&mut Machine::from_string(&buffer).unwrap()).unwrap();
+        assert_eq!(part1(&ship).unwrap(), 296);
+        assert_eq!(part2(&ship).unwrap(), 302);
+    }
+
+    #[test]
+    fn profiled() {
+        let buffer = std::fs::read_to_string("./inputs/day15.txt").unwrap();
+        let tracer = intcode::tracer::Tracer::new().shared();
+        let answers = crate::solution::run::<Day15>(&buffer, &[1], Some(&tracer)).unwrap();
+        assert_eq!(answers[0].as_ref().unwrap(), &Some(Answer::from(296_u32)));
+        // the exploring happens while parsing, and is still counted.
+        assert!(tracer.lock().unwrap().total() > 0);
+    }
+
+    #[test]
+    fn scripted_exploration() {
+        let buffer = std::fs::read_to_string("./inputs/day15.txt").unwrap();
+        let mut machine = Machine::from_string(&buffer).unwrap();
+        let mut screen = Framebuffer::new();
+        let mut keys = Script::parse("up down left right b q").unwrap();
+        explore(&mut machine, &mut screen, &mut keys).unwrap();
+        assert!(keys.is_empty());
+
+        // the droid went left and back, then was taken back a step.
+        let snapshot = screen.snapshot();
+        let map: Vec<_> = snapshot.lines().skip(24).map(str::trim).collect();
+        assert_eq!(map, vec!["##", "@.#", "##"]);
+        assert_eq!(screen.get(24, 25), '@');
+    }
 }
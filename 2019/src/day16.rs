@@ -1,8 +1,10 @@
 use std::convert::TryFrom;
 
+use crate::error::Result;
 use crate::parsers::*;
+use crate::solution::{Answer, Solution};
 
-fn numbers_p(s: &[u8]) -> ParseResult<Vec<i32>> {
+fn numbers_p(s: &[u8]) -> ParseResult<'_, Vec<i32>> {
     many(token(|c| {
         char::from(c)
             .to_digit(10)
@@ -118,14 +120,35 @@ pub fn part2(signal: Vec<i32>, target: usize) -> Vec<i32> {
     real_signal.iter().take(8).copied().collect()
 }
 
-pub fn start() {
-    let buffer = std::fs::read_to_string("./inputs/day16.txt")
-        .expect("Something went wrong reading the file");
+/// The message offset is given by the first seven digits of the signal.
+fn message_offset(signal: &[i32]) -> usize {
+    signal.iter().take(7).fold(0, |offset, digit| {
+        offset * 10 + usize::try_from(*digit).unwrap()
+    })
+}
+
+fn message(digits: &[i32]) -> String {
+    digits
+        .iter()
+        .take(8)
+        .map(|digit| digit.to_string())
+        .collect()
+}
 
-    let numbers = numbers_p(buffer.as_bytes()).unwrap().1;
+pub struct Day16;
+impl Solution for Day16 {
+    type Input<'a> = Vec<i32>;
 
-    println!("numbers len: {:?}", numbers.len());
-    println!("Program Output: {:?}", &part2(numbers, 5_973_847));
+    fn parse(input: &str) -> Result<Vec<i32>> {
+        complete(numbers_p(input.as_bytes()), "a list of digits")
+    }
+    fn part1(signal: &Vec<i32>) -> Result<Answer> {
+        Ok(message(&part1(signal.clone())).into())
+    }
+    fn part2(signal: &Vec<i32>) -> Result<Answer> {
+        let offset = message_offset(signal);
+        Ok(message(&part2(signal.clone(), offset)).into())
+    }
 }
 
 #[cfg(test)]
@@ -201,7 +224,7 @@ mod tests {
     #[test]
     fn part_2_1() {
         let input = "03036732577212944063491565474664";
-        let numbers = numbers_p(input.as_bytes()).unwrap().1;
+        let numbers = Day16::parse(input).unwrap();
         assert_eq!(part2(numbers, 303_673), vec![8, 4, 4, 6, 2, 0, 2, 6]);
     }
 }
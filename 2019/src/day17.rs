@@ -1,22 +1,44 @@
-use std::convert::TryInto;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
 
-use crate::day05::Machine;
+use intcode::tracer::SharedTracer;
+use intcode::Machine;
 
-pub fn part1(mut machine: Machine) -> usize {
-    let buffer: Vec<char> = machine
-        .run(std::iter::empty())
+use crate::day05::last_output;
+use crate::day11::{Direction, Point};
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
+
+/// the longest a movement routine can be, not counting the newline.
+const ROUTINE_LENGTH: usize = 20;
+const FUNCTIONS: [char; 3] = ['A', 'B', 'C'];
+
+/// The camera image, one row per line.
+fn camera(machine: &mut Machine) -> Result<Vec<Vec<char>>> {
+    let buffer = machine
+        .run(std::iter::empty())?
         .into_iter()
         .map(|char_num| {
-            let char_8: u8 = char_num.try_into().unwrap();
-            char::from(char_8)
+            u8::try_from(char_num).map(char::from).map_err(|_| {
+                Error::no_solution(format!("the camera sent {}, not a character", char_num))
+            })
         })
-        .collect();
+        .collect::<Result<Vec<char>>>()?;
+
+    Ok(buffer
+        .split(|c| *c == '\n')
+        .filter(|line| !line.is_empty())
+        .map(<[char]>::to_vec)
+        .collect())
+}
 
-    let grid: Vec<&[char]> = buffer.split(|c| *c == '\n').collect();
+pub fn part1(mut machine: Machine) -> Result<usize> {
+    let grid = camera(&mut machine)?;
 
     let mut sum = 0;
 
-    for (y, line) in grid.iter().copied().enumerate() {
+    for (y, line) in grid.iter().enumerate() {
         for (x, c) in line.iter().copied().enumerate() {
             if c != '#' || y == 0 || x == 0 {
                 continue;
@@ -38,19 +60,225 @@ pub fn part1(mut machine: Machine) -> usize {
         }
     }
 
-    sum
+    Ok(sum)
+}
+
+/// A turn followed by a number of steps forward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    Left(usize),
+    Right(usize),
+}
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Move::Left(steps) => write!(f, "L,{}", steps),
+            Move::Right(steps) => write!(f, "R,{}", steps),
+        }
+    }
+}
+
+fn is_scaffold(grid: &[Vec<char>], point: Point) -> bool {
+    let (x, y) = match (usize::try_from(point.x()), usize::try_from(point.y())) {
+        (Ok(x), Ok(y)) => (x, y),
+        _ => return false,
+    };
+    grid.get(y).and_then(|row| row.get(x)) == Some(&'#')
+}
+
+/// Follow the scaffold from the robot to its end, turning only when it
+/// can't go straight on.
+pub fn trace(grid: &[Vec<char>]) -> Result<Vec<Move>> {
+    let robot = grid.iter().enumerate().find_map(|(y, row)| {
+        row.iter().enumerate().find_map(|(x, &c)| {
+            let facing = match c {
+                '^' => Direction::Up,
+                '>' => Direction::Right,
+                'v' => Direction::Down,
+                '<' => Direction::Left,
+                _ => return None,
+            };
+            Some((Point(x as i32, y as i32), facing))
+        })
+    });
+    let (mut position, mut facing) =
+        robot.ok_or_else(|| Error::no_solution("the robot isn't on the camera"))?;
+
+    let ahead = |position: Point, direction: Direction| {
+        let mut next = position;
+        next.step(direction);
+        is_scaffold(grid, next)
+    };
+
+    let mut moves = Vec::new();
+    loop {
+        let mut left = facing;
+        left.turn_ccw();
+        let mut right = facing;
+        right.turn_cw();
+        let turn: fn(usize) -> Move = if ahead(position, left) {
+            facing = left;
+            Move::Left
+        } else if ahead(position, right) {
+            facing = right;
+            Move::Right
+        } else {
+            break;
+        };
+
+        let mut steps = 0;
+        while ahead(position, facing) {
+            position.step(facing);
+            steps += 1;
+        }
+        moves.push(turn(steps));
+    }
+    Ok(moves)
+}
+
+fn join<T: ToString>(items: &[T]) -> String {
+    let items: Vec<_> = items.iter().map(T::to_string).collect();
+    items.join(",")
+}
+
+/// The movement routines for the robot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Routines {
+    /// indices into `functions`.
+    pub main: Vec<usize>,
+    pub functions: Vec<Vec<Move>>,
+}
+impl Routines {
+    /// the routines as the robot reads them, without the video feed answer.
+    pub fn to_ascii(&self) -> String {
+        let main: Vec<char> = self.main.iter().map(|&index| FUNCTIONS[index]).collect();
+        let mut lines = vec![join(&main)];
+        for index in 0..FUNCTIONS.len() {
+            lines.push(self.functions.get(index).map_or(String::new(), |f| join(f)));
+        }
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+}
+
+fn fits(moves: &[Move]) -> bool {
+    join(moves).len() <= ROUTINE_LENGTH
+}
+
+/// Split `path` into calls to at most three functions, every routine
+/// fitting in 20 characters. Existing functions are tried before new ones.
+pub fn compress(path: &[Move]) -> Option<Routines> {
+    fn search<'a>(
+        path: &'a [Move],
+        main: &mut Vec<usize>,
+        functions: &mut Vec<&'a [Move]>,
+    ) -> bool {
+        if path.is_empty() {
+            return true;
+        }
+        // each call takes two characters with its comma.
+        if main.len() * 2 + 1 > ROUTINE_LENGTH {
+            return false;
+        }
+        for index in 0..functions.len() {
+            let function = functions[index];
+            if path.starts_with(function) {
+                main.push(index);
+                if search(&path[function.len()..], main, functions) {
+                    return true;
+                }
+                main.pop();
+            }
+        }
+        if functions.len() < FUNCTIONS.len() {
+            for len in (1..=path.len()).take_while(|&len| fits(&path[..len])) {
+                functions.push(&path[..len]);
+                main.push(functions.len() - 1);
+                if search(&path[len..], main, functions) {
+                    return true;
+                }
+                main.pop();
+                functions.pop();
+            }
+        }
+        false
+    }
+
+    let mut main = Vec::new();
+    let mut functions = Vec::new();
+    if search(path, &mut main, &mut functions) {
+        Some(Routines {
+            main,
+            functions: functions.into_iter().map(<[Move]>::to_vec).collect(),
+        })
+    } else {
+        None
+    }
+}
+
+/// Work out the movement routines from the camera and run the robot over
+/// the scaffold, it reports the dust collected as its last output.
+pub fn part2(mut machine: Machine) -> Result<i64> {
+    let grid = camera(&mut machine.clone())?;
+    let path = trace(&grid)?;
+    let routines = compress(&path)
+        .ok_or_else(|| Error::no_solution("the path doesn't fit in three functions"))?;
+
+    machine.mem_set(0, 2);
+    let input = format!("{}n\n", routines.to_ascii());
+    last_output(&machine.run(input.bytes().map(i64::from))?)
 }
 
-pub fn part2(mut machine: Machine) {
+/// enter the movement routines by hand, returning the dust the robot
+/// collected if it accepted them.
+pub fn play_by_hand(mut machine: Machine) -> Result<Option<i64>> {
     machine.mem_set(0, 2);
-    machine.terminal();
+    let transcript = machine.terminal(io::stdin().lock(), io::stdout())?;
+    Ok(transcript
+        .last()
+        .copied()
+        .filter(|&dust| u8::try_from(dust).is_err()))
+}
+
+pub struct Day17;
+impl Solution for Day17 {
+    type Input<'a> = Machine;
+
+    fn parse(input: &str) -> Result<Machine> {
+        Ok(Machine::from_string(input)?)
+    }
+    fn trace(machine: &mut Machine, tracer: &SharedTracer) {
+        machine.set_tracer(Some(tracer.clone()));
+    }
+    fn part1(machine: &Machine) -> Result<Answer> {
+        Ok(part1(machine.clone())?.into())
+    }
+    fn part2(machine: &Machine) -> Result<Answer> {
+        Ok(part2(machine.clone())?.into())
+    }
 }
 
-pub fn start() {
-    let buffer = std::fs::read_to_string("./inputs/day17.txt")
-        .expect("Something went wrong reading the file");
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let machine = Machine::from_string(&buffer);
+    #[test]
+    fn compression() {
+        let buffer = std::fs::read_to_string("./inputs/day17.txt").unwrap();
+        let machine = Machine::from_string(&buffer).unwrap();
+        let grid = camera(&mut machine.clone()).unwrap();
+        let path = trace(&grid).unwrap();
+        let routines = compress(&path).unwrap();
 
-    println!("Program Output: {:?}", part2(machine));
+        // the routines as worked out by hand.
+        let by_hand = std::fs::read_to_string("./inputs/day17instructions.txt").unwrap();
+        assert!(by_hand.starts_with(&routines.to_ascii()));
+        assert_eq!(part2(machine).unwrap(), 752491);
+    }
+
+    #[test]
+    fn camera_garbage() {
+        // a '#' then something that isn't a character.
+        let mut machine = Machine::from_string("104,35,104,1000,99").unwrap();
+        assert!(camera(&mut machine).is_err());
+    }
 }
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
 use crate::day11::Direction;
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 struct Point {
@@ -11,13 +13,15 @@ impl Point {
     pub fn new(x: usize, y: usize) -> Self {
         Point { x, y }
     }
-    pub fn step(&mut self, direction: Direction) {
-        match direction {
-            Direction::Up => self.y -= 1,
-            Direction::Right => self.x += 1,
-            Direction::Down => self.y += 1,
-            Direction::Left => self.x -= 1,
-        }
+    /// the neighbouring point, or `None` off the top or left of the grid.
+    pub fn step(self, direction: Direction) -> Option<Self> {
+        let Point { x, y } = self;
+        Some(match direction {
+            Direction::Up => Point::new(x, y.checked_sub(1)?),
+            Direction::Right => Point::new(x + 1, y),
+            Direction::Down => Point::new(x, y + 1),
+            Direction::Left => Point::new(x.checked_sub(1)?, y),
+        })
     }
 }
 
@@ -61,11 +65,11 @@ impl std::fmt::Display for Cell {
             Cell::Empty => write!(f, "{}  {}", ANSI_BLACK, ANSI_RESET),
             Cell::Player => write!(f, " @"),
             Cell::Key(c) => {
-                let name: char = c.clone().into();
+                let name: char = (*c).into();
                 write!(f, "{} {}{}", ANSI_RED, name, ANSI_RESET)
             }
             Cell::Door(c) => {
-                let mut name: char = c.clone().into();
+                let mut name: char = (*c).into();
                 name.make_ascii_uppercase();
                 write!(f, "{} {}{}", ANSI_GREEN, name, ANSI_RESET)
             }
@@ -88,15 +92,10 @@ impl From<u8> for Cell {
     }
 }
 
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
 struct Keys {
     keys: u32,
 }
-impl Default for Keys {
-    fn default() -> Self {
-        Keys { keys: 0 }
-    }
-}
 impl Keys {
     fn has_key(self, key: u8) -> bool {
         let index = key - b'a';
@@ -119,19 +118,56 @@ impl MazeState {
     }
 }
 
+#[derive(Clone)]
 pub struct Maze {
     grid: Vec<Vec<Cell>>,
 }
 impl Maze {
-    fn from_str(input: &str) -> Self {
-        let grid: Vec<_> = input
-            .trim()
-            .as_bytes()
-            .split(|c| *c == b'\n')
+    fn from_str(input: &str) -> Result<Self> {
+        let lines: Vec<_> = input.trim().lines().map(str::as_bytes).collect();
+        let width = lines.first().map_or(0, |line| line.len());
+        if width == 0 {
+            return Err(Error::parse("the maze is empty"));
+        }
+
+        let height = lines.len();
+        for (y, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(Error::parse(format!(
+                    "row {} is {} wide, expected {}",
+                    y + 1,
+                    line.len(),
+                    width
+                )));
+            }
+            for (x, &c) in line.iter().enumerate() {
+                if !matches!(c, b'#' | b'.' | b'@') && !c.is_ascii_alphabetic() {
+                    return Err(Error::parse(format!(
+                        "unexpected {:?} at ({}, {})",
+                        char::from(c),
+                        x,
+                        y
+                    )));
+                }
+                let edge = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+                if edge && c != b'#' {
+                    return Err(Error::parse(format!(
+                        "the maze is not walled in at ({}, {})",
+                        x, y
+                    )));
+                }
+            }
+        }
+
+        let grid = lines
+            .iter()
             .map(|line| line.iter().map(|c| Cell::from(*c)).collect())
             .collect();
-
-        Maze { grid }
+        let maze = Maze { grid };
+        if maze.get_players().is_empty() {
+            return Err(Error::parse("the maze has no entrance"));
+        }
+        Ok(maze)
     }
 
     fn get_players(&self) -> Vec<Point> {
@@ -146,6 +182,27 @@ impl Maze {
         players
     }
 
+    /// Replace a lone entrance with the four separate vaults used in part 2.
+    /// Mazes that already have four entrances are left alone.
+    fn split_vault(&mut self) {
+        let players = self.get_players();
+        if players.len() != 1 {
+            return;
+        }
+
+        let Point { x, y } = players[0];
+        let layout: [&[u8; 3]; 3] = [b"@#@", b"###", b"@#@"];
+        for (dy, row) in layout.iter().enumerate() {
+            for (dx, c) in row.iter().enumerate() {
+                // the entrance is never on the outer wall, so this stays on the grid.
+                let index = Point::new(x + dx - 1, y + dy - 1);
+                if let Some(cell) = self.get_cell_mut(index) {
+                    *cell = Cell::from(*c);
+                }
+            }
+        }
+    }
+
     fn width(&self) -> usize {
         self.grid[0].len()
     }
@@ -166,10 +223,10 @@ impl Maze {
         let mut wall_count = 0;
 
         Direction::for_each(|direction| {
-            let mut new_index = index;
-            new_index.step(direction);
-
-            if self.get_cell(new_index).is_wall() {
+            let is_wall = index
+                .step(direction)
+                .map_or(true, |new_index| self.get_cell(new_index).is_wall());
+            if is_wall {
                 wall_count += 1;
             }
         });
@@ -197,18 +254,16 @@ impl Maze {
                     }
 
                     Direction::for_each(|direction| {
-                        let mut new_index = index;
-                        new_index.step(direction);
-
-                        cells_to_go.push(new_index);
+                        cells_to_go.extend(index.step(direction));
                     });
                 }
             }
         }
     }
 
-    fn naive_solve(&self) -> (Keys, u32) {
-        let initial_state = MazeState::new(self.get_players()[0], Keys::default());
+    fn naive_solve(&self) -> Option<(Keys, u32)> {
+        let start = *self.get_players().first()?;
+        let initial_state = MazeState::new(start, Keys::default());
         let mut best_so_far = HashMap::new();
 
         let mut best_keys = HashMap::new();
@@ -229,38 +284,40 @@ impl Maze {
                 }
             }
 
-            let current_best = best_so_far.entry(state).or_insert_with(u32::max_value);
+            let current_best = best_so_far.entry(state).or_insert(u32::MAX);
             if steps >= *current_best {
                 continue;
             }
             *current_best = steps;
 
             if cell.is_key() {
-                let best_key = best_keys.entry(state.keys).or_insert_with(u32::max_value);
+                let best_key = best_keys.entry(state.keys).or_insert(u32::MAX);
                 *best_key = steps.min(*best_key);
             }
 
             Direction::for_each(|direction| {
-                let mut position = state.position;
-                position.step(direction);
-
-                let new_state = MazeState::new(position, state.keys);
-                search_space.push((new_state, steps + 1));
+                if let Some(position) = state.position.step(direction) {
+                    let new_state = MazeState::new(position, state.keys);
+                    search_space.push((new_state, steps + 1));
+                }
             })
         }
 
-        // could be replaced by maximim by
-        best_keys
-            .into_iter()
-            .max_by_key(|(keys, _steps)| *keys)
-            .unwrap()
+        // a maze without keys is already solved.
+        Some(
+            best_keys
+                .into_iter()
+                .max_by_key(|(keys, _steps)| *keys)
+                .unwrap_or_default(),
+        )
     }
 
     fn co_solve(&self) -> usize {
         let mut total_area = 0;
         let mut longest_paths = 0;
 
-        for player in self.get_players() {
+        let players = self.get_players();
+        for &player in &players {
             let mut best_so_far = HashMap::new();
             let mut search_space = vec![(player, 0)];
 
@@ -270,25 +327,24 @@ impl Maze {
                     continue;
                 }
 
-                let current_best = best_so_far.entry(position).or_insert_with(usize::max_value);
+                let current_best = best_so_far.entry(position).or_insert(usize::MAX);
                 if steps >= *current_best {
                     continue;
                 }
                 *current_best = steps;
 
                 Direction::for_each(|direction| {
-                    let mut new_position = position;
-                    new_position.step(direction);
-
-                    search_space.push((new_position, steps + 1));
+                    if let Some(new_position) = position.step(direction) {
+                        search_space.push((new_position, steps + 1));
+                    }
                 })
             }
             total_area += best_so_far.len();
-            longest_paths += best_so_far.values().copied().max().unwrap();
+            longest_paths += best_so_far.values().copied().max().unwrap_or(0);
         }
 
         // subtract 2 for each player.
-        total_area * 2 - longest_paths - 8
+        (total_area * 2).saturating_sub(longest_paths + 2 * players.len())
     }
 }
 impl std::fmt::Display for Maze {
@@ -303,27 +359,58 @@ impl std::fmt::Display for Maze {
     }
 }
 
-pub fn part1() {
-    let buffer = std::fs::read_to_string("./inputs/day18.txt")
-        .expect("Something went wrong reading the file");
+pub fn part1(mut maze: Maze) -> Result<u32> {
+    maze.remove_dead_ends();
+    let (_keys, steps) = maze
+        .naive_solve()
+        .ok_or_else(|| Error::no_solution("the maze has no entrance"))?;
+    Ok(steps)
+}
 
-    let mut maze = Maze::from_str(&buffer);
+pub fn part2(mut maze: Maze) -> Result<usize> {
+    maze.split_vault();
     maze.remove_dead_ends();
-    println!("{}", maze);
-    println!("{:?}", maze.naive_solve());
+    Ok(maze.co_solve())
 }
 
-pub fn part2() {
-    let buffer = std::fs::read_to_string("./inputs/day18_2.txt")
-        .expect("Something went wrong reading the file");
+pub struct Day18;
+impl Solution for Day18 {
+    type Input<'a> = Maze;
 
-    let mut maze = Maze::from_str(&buffer);
-    println!("{}", maze);
-    maze.remove_dead_ends();
-    println!("{}", maze);
-    println!("{}", maze.co_solve());
+    fn parse(input: &str) -> Result<Maze> {
+        Maze::from_str(input)
+    }
+    fn part1(maze: &Maze) -> Result<Answer> {
+        Ok(part1(maze.clone())?.into())
+    }
+    fn part2(maze: &Maze) -> Result<Answer> {
+        Ok(part2(maze.clone())?.into())
+    }
 }
 
-pub fn start() {
-    println!("Program Output: {:?}", part1());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed() {
+        for input in [
+            "",
+            "#####\n#.@.#",
+            "#####\n#.@..\n#####",
+            "###\n#.#\n###",
+            "###\n#@?#\n###",
+        ] {
+            assert!(Day18::parse(input).is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn part1_examples() {
+        for (file, steps) in [("day18ex1", 8), ("day18ex2", 86)] {
+            let buffer = std::fs::read_to_string(format!("./inputs/{}.txt", file)).unwrap();
+            let maze = Maze::from_str(&buffer).unwrap();
+            assert_eq!(part1(maze).unwrap(), steps, "{}", file);
+        }
+    }
 }
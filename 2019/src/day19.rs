@@ -1,50 +1,62 @@
-use crate::day05::Machine;
+use intcode::tracer::SharedTracer;
+use intcode::Machine;
 
-fn affected(machine: &Machine, x: i64, y: i64) -> bool {
-    let output = machine.clone().run([x, y].iter().copied());
-    output[0] == 1
+use crate::day05::last_output;
+use crate::error::Result;
+use crate::solution::{Answer, Solution};
+
+fn affected(machine: &Machine, x: i64, y: i64) -> Result<bool> {
+    let output = machine.clone().run([x, y].iter().copied())?;
+    Ok(last_output(&output)? == 1)
 }
 
 /// question asks for default extent of 50.
-pub fn part1(extent: i64) -> i64 {
-    let buffer = std::fs::read_to_string("./inputs/day19.txt")
-        .expect("Something went wrong reading the file");
-    let machine = Machine::from_string(&buffer);
-
+pub fn part1(machine: &Machine, extent: i64) -> Result<i64> {
     let mut num_affected = 0;
 
     for x in 0..extent {
         for y in 0..extent {
-            let output = machine.clone().run([x, y].iter().copied());
-            num_affected += output[0];
+            if affected(machine, x, y)? {
+                num_affected += 1;
+            }
         }
     }
-    num_affected
+    Ok(num_affected)
 }
 
 /// question asks for default extent of 100.
-pub fn part2(extent: i64) -> i64 {
-    let buffer = std::fs::read_to_string("./inputs/day19.txt")
-        .expect("Something went wrong reading the file");
-    let machine = Machine::from_string(&buffer);
-
+pub fn part2(machine: &Machine, extent: i64) -> Result<i64> {
     let mut left = 0;
     let mut bottom = 100;
     loop {
-        if !affected(&machine, left, bottom) {
+        if !affected(machine, left, bottom)? {
             left += 1;
             continue;
         }
         let top = bottom - extent + 1;
         let right = left + extent - 1;
-        if !affected(&machine, right, top) {
+        if !affected(machine, right, top)? {
             bottom += 1;
             continue;
         }
-        return left * 10_000 + top;
+        return Ok(left * 10_000 + top);
     }
 }
 
-pub fn start() {
-    println!("Program Output: {:?}", part2(100));
+pub struct Day19;
+impl Solution for Day19 {
+    type Input<'a> = Machine;
+
+    fn parse(input: &str) -> Result<Machine> {
+        Ok(Machine::from_string(input)?)
+    }
+    fn trace(machine: &mut Machine, tracer: &SharedTracer) {
+        machine.set_tracer(Some(tracer.clone()));
+    }
+    fn part1(machine: &Machine) -> Result<Answer> {
+        Ok(part1(machine, 50)?.into())
+    }
+    fn part2(machine: &Machine) -> Result<Answer> {
+        Ok(part2(machine, 100)?.into())
+    }
 }
@@ -2,18 +2,21 @@ use std::cmp::Reverse;
 use std::collections::{BinaryHeap, HashMap};
 
 use crate::day11::Direction;
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 
 #[must_use]
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 struct Point(usize, usize);
 impl Point {
-    pub fn step(self, direction: Direction) -> Self {
-        match direction {
-            Direction::Up => Point(self.0, self.1 - 1),
+    /// the neighbouring point, or `None` off the top or left of the grid.
+    pub fn step(self, direction: Direction) -> Option<Self> {
+        Some(match direction {
+            Direction::Up => Point(self.0, self.1.checked_sub(1)?),
             Direction::Down => Point(self.0, self.1 + 1),
-            Direction::Left => Point(self.0 - 1, self.1),
+            Direction::Left => Point(self.0.checked_sub(1)?, self.1),
             Direction::Right => Point(self.0 + 1, self.1),
-        }
+        })
     }
 }
 
@@ -56,9 +59,6 @@ impl std::fmt::Display for Cell {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Portal(Point, Cell);
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct MazeState(Point, Level);
 impl MazeState {
@@ -85,51 +85,79 @@ impl Level {
 }
 
 const MARGIN: usize = 4;
+#[derive(Clone)]
 pub struct Maze {
     portals: HashMap<Cell, Point>,
     grid: Vec<Vec<Cell>>,
 }
 impl Maze {
-    fn from_str(input: &str) -> Self {
+    fn from_str(input: &str) -> Result<Self> {
+        let char_grid: Vec<_> = input.lines().map(str::as_bytes).collect();
+        let width = char_grid.first().map_or(0, |line| line.len());
+        if let Some(y) = char_grid.iter().position(|line| line.len() != width) {
+            return Err(Error::parse(format!(
+                "row {} is {} wide, expected {}",
+                y + 1,
+                char_grid[y].len(),
+                width
+            )));
+        }
+
+        // passages need two cells of margin on each side for portal labels.
+        let height = char_grid.len();
+        for (y, line) in char_grid.iter().enumerate() {
+            for (x, &c) in line.iter().enumerate() {
+                if c == b'.' && (x < 2 || y < 2 || x + 2 >= width || y + 2 >= height) {
+                    return Err(Error::parse(format!(
+                        "the passage at ({}, {}) runs into the margin",
+                        x, y
+                    )));
+                }
+            }
+        }
+
         let mut portals = HashMap::new();
-        let char_grid: Vec<_> = input.as_bytes().split(|c| *c == b'\n').collect();
-
-        let grid = char_grid
-            .iter()
-            .enumerate()
-            .map(|(y, &line)| {
-                line.iter()
-                    .enumerate()
-                    .map(|(x, _)| {
-                        let cell = Maze::coord_to_cell(&char_grid, Point(x, y));
-
-                        match cell {
-                            Cell::InnerPortal(_, _) | Cell::OuterPortal(_, _) => {
-                                portals.insert(cell, Point(x, y));
-                            }
-                            Cell::Wall | Cell::Empty => {}
-                        }
+        let mut grid = Vec::with_capacity(height);
+        for y in 0..height {
+            let mut row = Vec::with_capacity(width);
+            for x in 0..width {
+                let cell = Maze::coord_to_cell(&char_grid, Point(x, y))?;
+
+                match cell {
+                    Cell::InnerPortal(_, _) | Cell::OuterPortal(_, _) => {
+                        portals.insert(cell, Point(x, y));
+                    }
+                    Cell::Wall | Cell::Empty => {}
+                }
 
-                        cell
-                    })
-                    .collect()
-            })
-            .collect();
+                row.push(cell);
+            }
+            grid.push(row);
+        }
 
-        Maze { portals, grid }
+        if !portals.contains_key(&Cell::OuterPortal(b'A', b'A')) {
+            return Err(Error::parse("the maze has no AA portal"));
+        }
+        Ok(Maze { portals, grid })
     }
 
-    fn coord_to_cell(char_grid: &[&[u8]], position: Point) -> Cell {
+    /// `position` must be on the grid, and a passage must be inside the margin.
+    fn coord_to_cell(char_grid: &[&[u8]], position: Point) -> Result<Cell> {
         let character = char_grid[position.1][position.0];
         if character != b'.' {
-            return Cell::Wall;
+            return Ok(Cell::Wall);
         }
 
-        let mut result = Cell::Empty;
+        let mut result = Ok(Cell::Empty);
 
         Direction::for_each(|direction| {
-            let mut first_pos = position.step(direction);
-            let mut second_pos = first_pos.step(direction);
+            let label = position
+                .step(direction)
+                .and_then(|first_pos| Some((first_pos, first_pos.step(direction)?)));
+            let (mut first_pos, mut second_pos) = match label {
+                Some(label) => label,
+                None => return,
+            };
 
             if first_pos > second_pos {
                 std::mem::swap(&mut first_pos, &mut second_pos);
@@ -139,7 +167,13 @@ impl Maze {
             let second_char = char_grid[second_pos.1][second_pos.0];
 
             if first_char.is_ascii_alphabetic() && second_char.is_ascii_alphabetic() {
-                assert_eq!(result, Cell::Empty);
+                if !matches!(result, Ok(Cell::Empty)) {
+                    result = Err(Error::parse(format!(
+                        "the passage at ({}, {}) has more than one portal",
+                        position.0, position.1
+                    )));
+                    return;
+                }
 
                 let y = position.1;
                 let x = position.0;
@@ -149,9 +183,9 @@ impl Maze {
                     && x > MARGIN
                     && x < char_grid[y].len() - MARGIN
                 {
-                    result = Cell::InnerPortal(first_char, second_char);
+                    result = Ok(Cell::InnerPortal(first_char, second_char));
                 } else {
-                    result = Cell::OuterPortal(first_char, second_char);
+                    result = Ok(Cell::OuterPortal(first_char, second_char));
                 }
             }
         });
@@ -159,7 +193,7 @@ impl Maze {
     }
 
     fn width(&self) -> usize {
-        self.grid[2].len()
+        self.grid[0].len()
     }
     fn height(&self) -> usize {
         self.grid.len()
@@ -179,7 +213,10 @@ impl Maze {
         let mut wall_count = 0;
 
         Direction::for_each(|direction| {
-            if self.get_cell(index.step(direction)).is_wall() {
+            let is_wall = index
+                .step(direction)
+                .map_or(true, |new_index| self.get_cell(new_index).is_wall());
+            if is_wall {
                 wall_count += 1;
             }
         });
@@ -207,7 +244,7 @@ impl Maze {
                     }
 
                     Direction::for_each(|direction| {
-                        cells_to_go.push(index.step(direction));
+                        cells_to_go.extend(index.step(direction));
                     });
                 }
             }
@@ -222,16 +259,16 @@ impl Maze {
         }
     }
 
-    fn get_aa(&self) -> Point {
-        *self.portals.get(&Cell::OuterPortal(b'A', b'A')).unwrap()
+    fn get_aa(&self) -> Option<Point> {
+        self.portals.get(&Cell::OuterPortal(b'A', b'A')).copied()
     }
 
-    fn aa_to_zz(&self) -> u32 {
-        let start = self.get_aa();
+    fn aa_to_zz(&self) -> Option<u32> {
+        let start = self.get_aa()?;
 
         let mut best_so_far = HashMap::new();
         let mut search_space = vec![(start, 0)];
-        let mut current_best_solve = u32::max_value();
+        let mut current_best_solve = u32::MAX;
 
         while let Some((position, steps)) = search_space.pop() {
             let cell = self.get_cell(position);
@@ -239,7 +276,7 @@ impl Maze {
                 continue;
             }
 
-            let current_best = best_so_far.entry(position).or_insert_with(u32::max_value);
+            let current_best = best_so_far.entry(position).or_insert(u32::MAX);
             if steps >= *current_best {
                 continue;
             }
@@ -258,15 +295,17 @@ impl Maze {
             }
 
             Direction::for_each(|direction| {
-                search_space.push((position.step(direction), steps + 1));
+                if let Some(new_position) = position.step(direction) {
+                    search_space.push((new_position, steps + 1));
+                }
             })
         }
 
-        current_best_solve
+        Some(current_best_solve).filter(|&steps| steps != u32::MAX)
     }
 
     fn aa_to_zz_rec(&self) -> Option<u32> {
-        let start = MazeState(self.get_aa(), Level(0));
+        let start = MazeState(self.get_aa()?, Level(0));
 
         let mut best_so_far = HashMap::new();
         let mut search_space = BinaryHeap::new();
@@ -278,7 +317,7 @@ impl Maze {
                 continue;
             }
 
-            let current_best = best_so_far.entry(state).or_insert_with(u32::max_value);
+            let current_best = best_so_far.entry(state).or_insert(u32::MAX);
             if steps >= *current_best {
                 continue;
             }
@@ -306,8 +345,10 @@ impl Maze {
             }
 
             Direction::for_each(|direction| {
-                let new_state = MazeState(state.position().step(direction), state.level());
-                search_space.push(Reverse((steps + 1, new_state)));
+                if let Some(new_position) = state.position().step(direction) {
+                    let new_state = MazeState(new_position, state.level());
+                    search_space.push(Reverse((steps + 1, new_state)));
+                }
             })
         }
         None
@@ -325,51 +366,70 @@ impl std::fmt::Display for Maze {
     }
 }
 
-pub fn part1(mut maze: Maze) -> u32 {
+pub fn part1(mut maze: Maze) -> Option<u32> {
     maze.remove_dead_ends();
-    println!("{}", maze);
     maze.aa_to_zz()
 }
 
 pub fn part2(mut maze: Maze) -> Option<u32> {
     maze.remove_dead_ends();
-    println!("{}", maze);
     maze.aa_to_zz_rec()
 }
 
-pub fn start() {
-    let buffer = std::fs::read_to_string("./inputs/day20.txt")
-        .expect("Something went wrong reading the file");
+pub struct Day20;
+impl Solution for Day20 {
+    type Input<'a> = Maze;
 
-    let maze = Maze::from_str(&buffer);
-    println!("Program Output: {:?}", part2(maze));
+    fn parse(input: &str) -> Result<Maze> {
+        Maze::from_str(input)
+    }
+    fn part1(maze: &Maze) -> Result<Answer> {
+        let steps = part1(maze.clone()).ok_or_else(|| Error::no_solution("no route to ZZ"))?;
+        Ok(steps.into())
+    }
+    fn part2(maze: &Maze) -> Result<Answer> {
+        let steps = part2(maze.clone()).ok_or_else(|| Error::no_solution("no route to ZZ"))?;
+        Ok(steps.into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn malformed() {
+        for input in [
+            "",
+            "#####\n.....",
+            "  #.#\n  #.#\n  ###",
+            "    \n  ..\n    \n    \n",
+        ] {
+            assert!(Day20::parse(input).is_err(), "{:?}", input);
+        }
+    }
+
     #[test]
     fn part1_ex1() {
         let buffer = std::fs::read_to_string("./inputs/day20ex1.txt").unwrap();
 
-        let maze = Maze::from_str(&buffer);
-        assert_eq!(part1(maze), 23);
+        let maze = Maze::from_str(&buffer).unwrap();
+        assert_eq!(part1(maze), Some(23));
     }
 
     #[test]
     fn part1_ex2() {
         let buffer = std::fs::read_to_string("./inputs/day20ex2.txt").unwrap();
 
-        let maze = Maze::from_str(&buffer);
-        assert_eq!(part1(maze), 58);
+        let maze = Maze::from_str(&buffer).unwrap();
+        assert_eq!(part1(maze), Some(58));
     }
 
     #[test]
     fn part2_ex1() {
         let buffer = std::fs::read_to_string("./inputs/day20ex1.txt").unwrap();
 
-        let maze = Maze::from_str(&buffer);
+        let maze = Maze::from_str(&buffer).unwrap();
         assert_eq!(part2(maze), Some(26));
     }
 }
@@ -1,20 +1,50 @@
-use crate::day05::Machine;
+use std::convert::TryFrom;
+use std::io;
 
-pub fn part1(mut machine: Machine) {
-    println!("Memory used: {}", machine.mem_size());
-    machine.print_mem();
-    print!("\n\n\n---------\n\n\n");
-    machine.terminal();
-    print!("\n\n\n---------\n\n\n");
-    println!("Memory used: {}", machine.mem_size());
-    machine.print_mem();
+use intcode::tracer::SharedTracer;
+use intcode::Machine;
+
+use crate::error::Result;
+use crate::solution::{Answer, Solution};
+use crate::springscript::{self, Mode};
+
+/// search the springscript templates for one that walks the droid across
+/// the hull, returning the hull damage it reports.
+pub fn part1(machine: &Machine) -> Result<i64> {
+    let (_, damage) = springscript::search_templates(machine, Mode::Walk)?;
+    Ok(damage)
 }
 
-pub fn start() {
-    let buffer = std::fs::read_to_string("./inputs/day21.txt")
-        .expect("Something went wrong reading the file");
+/// the same with all nine sensors, running.
+pub fn part2(machine: &Machine) -> Result<i64> {
+    let (_, damage) = springscript::search_templates(machine, Mode::Run)?;
+    Ok(damage)
+}
+
+/// enter the springscript by hand, returning the hull damage if the droid
+/// made it across.
+pub fn play_by_hand(mut machine: Machine) -> Result<Option<i64>> {
+    let transcript = machine.terminal(io::stdin().lock(), io::stdout())?;
+    Ok(transcript
+        .last()
+        .copied()
+        .filter(|&damage| u8::try_from(damage).is_err()))
+}
 
-    let machine = Machine::from_string(&buffer);
+pub struct Day21;
+impl Solution for Day21 {
+    type Input<'a> = Machine;
 
-    println!("Program Output: {:?}", part1(machine));
+    fn parse(input: &str) -> Result<Machine> {
+        Ok(Machine::from_string(input)?)
+    }
+    fn trace(machine: &mut Machine, tracer: &SharedTracer) {
+        machine.set_tracer(Some(tracer.clone()));
+    }
+    fn part1(machine: &Machine) -> Result<Answer> {
+        Ok(part1(machine)?.into())
+    }
+    fn part2(machine: &Machine) -> Result<Answer> {
+        Ok(part2(machine)?.into())
+    }
 }
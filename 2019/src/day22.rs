@@ -1,11 +1,13 @@
-use crate::parsers::{byte, chunk, optional, sep_by, take_while_p, ParseResult};
+use crate::error::Result;
+use crate::parsers::{byte, chunk, complete, optional, sep_by, take_while_p, ParseResult};
+use crate::solution::{Answer, Solution};
 
-fn positive_number(s: &[u8]) -> ParseResult<i128> {
+fn positive_number(s: &[u8]) -> ParseResult<'_, i128> {
     let (s, num_str) = take_while_p(|c| c.is_ascii_digit())(s)?;
-    let number: i128 = std::str::from_utf8(num_str).unwrap().parse().unwrap();
+    let number: i128 = std::str::from_utf8(num_str).ok()?.parse().ok()?;
     Some((s, number))
 }
-fn number(s: &[u8]) -> ParseResult<i128> {
+fn number(s: &[u8]) -> ParseResult<'_, i128> {
     let (s, opt_sign) = optional(byte(b'-'))(s)?;
     let (s, mut number) = positive_number(s)?;
 
@@ -14,24 +16,24 @@ fn number(s: &[u8]) -> ParseResult<i128> {
     }
     Some((s, number))
 }
-fn stack(s: &[u8]) -> ParseResult<Shuffle> {
+fn stack(s: &[u8]) -> ParseResult<'_, Shuffle> {
     let (s, _) = chunk(b"deal into new stack")(s)?;
     Some((s, Shuffle::Stack))
 }
-fn increment(s: &[u8]) -> ParseResult<Shuffle> {
+fn increment(s: &[u8]) -> ParseResult<'_, Shuffle> {
     let (s, _) = chunk(b"deal with increment ")(s)?;
     let (s, num) = positive_number(s)?;
     Some((s, Shuffle::Increment(num)))
 }
-fn cut(s: &[u8]) -> ParseResult<Shuffle> {
+fn cut(s: &[u8]) -> ParseResult<'_, Shuffle> {
     let (s, _) = chunk(b"cut ")(s)?;
     let (s, num) = number(s)?;
     Some((s, Shuffle::Cut(num)))
 }
-fn shuffle(s: &[u8]) -> ParseResult<Shuffle> {
+fn shuffle(s: &[u8]) -> ParseResult<'_, Shuffle> {
     stack(s).or_else(|| increment(s)).or_else(|| cut(s))
 }
-fn shuffles(s: &[u8]) -> ParseResult<Vec<Shuffle>> {
+fn shuffles(s: &[u8]) -> ParseResult<'_, Vec<Shuffle>> {
     sep_by(shuffle, byte(b'\n'))(s)
 }
 
@@ -60,10 +62,10 @@ pub struct Deck {
     shuffles: Vec<Shuffle>,
 }
 impl Deck {
-    pub fn from_str(len: i128, s: &str) -> Self {
+    pub fn from_shuffles(len: i128, shuffles: &[Shuffle]) -> Self {
         Deck {
             len,
-            shuffles: shuffles(s.as_bytes()).unwrap().1,
+            shuffles: shuffles.to_vec(),
         }
     }
     pub fn new(len: i128) -> Self {
@@ -106,8 +108,8 @@ pub enum Shuffle {
     Increment(i128),
 }
 
-pub fn part1(s: &str) -> i128 {
-    let deck = Deck::from_str(10_007, s);
+pub fn part1(shuffles: &[Shuffle]) -> i128 {
+    let deck = Deck::from_shuffles(10_007, shuffles);
     deck.position_of_card(2019)
 }
 
@@ -134,13 +136,20 @@ const STARTING_POSITION: i128 = 2020;
 const NUM_CARDS: i128 = 119_315_717_514_047;
 const SHUFFLES: i128 = 101_741_582_076_661;
 
-pub fn start() {
-    let buffer = std::fs::read_to_string("./inputs/day22.txt").unwrap();
-    let deck = Deck::from_str(NUM_CARDS, &buffer);
-    println!(
-        "Program Output: {:?}",
-        part2(SHUFFLES, STARTING_POSITION, &deck)
-    );
+pub struct Day22;
+impl Solution for Day22 {
+    type Input<'a> = Vec<Shuffle>;
+
+    fn parse(input: &str) -> Result<Vec<Shuffle>> {
+        complete(shuffles(input.as_bytes()), "one shuffle per line")
+    }
+    fn part1(shuffles: &Vec<Shuffle>) -> Result<Answer> {
+        Ok(part1(shuffles).into())
+    }
+    fn part2(shuffles: &Vec<Shuffle>) -> Result<Answer> {
+        let deck = Deck::from_shuffles(NUM_CARDS, shuffles);
+        Ok(part2(SHUFFLES, STARTING_POSITION, &deck).into())
+    }
 }
 
 #[cfg(test)]
@@ -1,101 +1,244 @@
 use std::collections::VecDeque;
 use std::convert::TryFrom;
 
-use crate::day05::Machine;
+use intcode::tracer::SharedTracer;
+use intcode::Machine;
 
-struct Network {
+use crate::error::{Error, Result};
+use crate::runtime::{Event, InputMode, Node, Output, Runtime, IDLE_POLLS};
+use crate::scheduler::{PacketStats, Scheduler};
+use crate::solution::{Answer, Solution};
+
+const NAT: i64 = 255;
+const NUM_MACHINES: usize = 50;
+
+/// The network run on one thread, the scheduler picking which machine runs
+/// each step.
+pub struct Network {
     machines: Vec<Machine>,
-    packets: VecDeque<[i64; 3]>,
-    current_idle: usize,
+    /// the values waiting to be read by each machine.
+    inboxes: Vec<VecDeque<i64>>,
+    /// packets in each inbox.
+    pending: Vec<usize>,
+    idle_polls: Vec<usize>,
+    scheduler: Scheduler,
     nat: Option<(i64, i64)>,
 }
 impl Network {
-    fn new(machine: &Machine, length: usize) -> Self {
-        let machines: Vec<_> = std::iter::repeat_with(|| machine.clone())
-            .take(length)
+    pub fn new(machine: &Machine, length: usize, mut scheduler: Scheduler) -> Self {
+        let machines = vec![machine.clone(); length];
+        // every machine reads its address first.
+        let inboxes = (0..length)
+            .map(|address| std::iter::once(i64::try_from(address).unwrap()).collect())
             .collect();
-        let packets = VecDeque::new();
-
-        let mut network = Network {
+        scheduler.start(length);
+        Network {
             machines,
-            packets,
-            current_idle: 0,
+            inboxes,
+            pending: vec![0; length],
+            idle_polls: vec![0; length],
+            scheduler,
             nat: None,
+        }
+    }
+
+    /// Run the machine the scheduler picks on everything waiting for it, or
+    /// `-1` if there's nothing, and queue up the packets it sends.
+    pub fn step(&mut self) -> Result<()> {
+        let index = self.scheduler.next(&self.pending);
+        let stats = self.scheduler.stats_mut(index);
+        let inbox = &mut self.inboxes[index];
+        let output = if inbox.is_empty() {
+            stats.polls += 1;
+            self.idle_polls[index] += 1;
+            self.machines[index].run(std::iter::once(-1))?
+        } else {
+            stats.received += self.pending[index];
+            self.pending[index] = 0;
+            self.idle_polls[index] = 0;
+            self.machines[index].run(inbox.drain(..))?
         };
 
-        for i in 0..network.machines.len() {
-            let address = i64::try_from(i).unwrap();
-            let output = network.machines[i].run(std::iter::once(address));
-            network.disperse(&output);
+        let packets = output.chunks_exact(3);
+        if !packets.remainder().is_empty() {
+            return Err(Error::no_solution(format!(
+                "machine {} sent a partial packet {:?}",
+                index,
+                packets.remainder()
+            )));
         }
-
-        network
-    }
-    fn disperse(&mut self, output: &[i64]) {
-        for packet in output.chunks_exact(3) {
-            match packet {
-                [addr, x, y] => self.packets.push_back([*addr, *x, *y]),
-                _ => unreachable!(),
+        for packet in packets {
+            self.scheduler.stats_mut(index).sent += 1;
+            self.idle_polls[index] = 0;
+            match (packet[0], usize::try_from(packet[0])) {
+                (NAT, _) => self.nat = Some((packet[1], packet[2])),
+                (_, Ok(addr)) if addr < self.machines.len() => {
+                    self.queue(addr, packet[1], packet[2])
+                }
+                (addr, _) => {
+                    return Err(Error::no_solution(format!(
+                        "machine {} sent a packet to {}",
+                        index, addr
+                    )))
+                }
             }
         }
+        Ok(())
+    }
+
+    fn queue(&mut self, addr: usize, x: i64, y: i64) {
+        self.inboxes[addr].extend([x, y].iter().copied());
+        self.pending[addr] += 1;
+        self.scheduler.queued(addr);
+    }
+
+    /// whether nothing is waiting to be received and no machine has sent
+    /// anything since it last found nothing to receive.
+    pub fn is_idle(&self) -> bool {
+        self.inboxes.iter().all(VecDeque::is_empty)
+            && self.idle_polls.iter().all(|&polls| polls >= IDLE_POLLS)
     }
-    fn step(&mut self) {
-        let output = if let Some(packet) = self.packets.pop_front() {
-            self.current_idle = 0;
 
-            let addr = usize::try_from(packet[0]).unwrap();
-            let x = packet[1];
-            let y = packet[2];
+    /// the last packet sent to the NAT.
+    pub fn nat(&self) -> Option<(i64, i64)> {
+        self.nat
+    }
 
-            if let Some(machine) = self.machines.get_mut(addr) {
-                machine.run([x, y].iter().copied())
-            } else {
-                assert!(addr == 255);
-                self.nat = Some((x, y));
-                return;
+    pub fn stats(&self) -> &[PacketStats] {
+        self.scheduler.stats()
+    }
+
+    /// Run until the NAT has a packet.
+    pub fn run_until_nat(&mut self) -> Result<(i64, i64)> {
+        loop {
+            if let Some(packet) = self.nat {
+                return Ok(packet);
             }
-        } else {
-            let current = self.current_idle;
-            self.current_idle += 1;
-            if self.current_idle >= self.machines.len() {
-                // idle
-                let (x, y) = self.nat.unwrap();
-                dbg!(y);
-                self.machines[0].run([x, y].iter().copied())
-            } else {
-                self.machines[current].run(std::iter::once(-1))
+            if self.is_idle() {
+                return Err(Error::no_solution("the network went idle"));
             }
-        };
+            self.step()?;
+        }
+    }
 
-        self.disperse(&output);
+    /// Run with the NAT waking machine 0 whenever the network is idle, until
+    /// it sends the same Y twice in a row, which is returned.
+    pub fn run_nat(&mut self) -> Result<i64> {
+        let mut last_y = None;
+        loop {
+            if self.is_idle() {
+                let (x, y) = self
+                    .nat
+                    .ok_or_else(|| Error::no_solution("idle with nothing for the NAT"))?;
+                if last_y == Some(y) {
+                    return Ok(y);
+                }
+                last_y = Some(y);
+                self.queue(0, x, y);
+            }
+            self.step()?;
+        }
     }
-    fn last_nat(&self) -> Option<(i64, i64)> {
-        self.nat
+}
+
+fn start(machine: &Machine) -> Result<Runtime> {
+    let nodes = (0..NUM_MACHINES)
+        .map(|address| {
+            Node::new(machine.clone(), InputMode::NonBlocking(-1), Output::Packets)
+                .with_initial(Some(i64::try_from(address).unwrap()))
+        })
+        .collect();
+    Runtime::start(nodes)
+}
+
+/// the Y of the first packet sent to the NAT, with every machine on its own
+/// thread.
+pub fn part1(machine: &Machine) -> Result<i64> {
+    let runtime = start(machine)?;
+    match runtime.next_event()? {
+        Event::Packet([NAT, _, y]) => Ok(y),
+        Event::Packet([addr, ..]) => {
+            Err(Error::no_solution(format!("a packet was sent to {}", addr)))
+        }
+        Event::Quiescent | Event::Halted => Err(Error::no_solution("the network went idle")),
     }
 }
 
-const NUM_MACHINES: usize = 50;
-pub fn part1(machine: &Machine) -> i64 {
-    let mut network = Network::new(machine, NUM_MACHINES);
+/// the first Y the NAT sends to machine 0 twice in a row.
+pub fn part2(machine: &Machine) -> Result<i64> {
+    let runtime = start(machine)?;
+    let mut nat = None;
+    let mut last_y = None;
     loop {
-        network.step();
-        if let Some((_, y)) = network.last_nat() {
-            break y;
+        match runtime.next_event()? {
+            Event::Packet([NAT, x, y]) => nat = Some((x, y)),
+            Event::Packet([addr, ..]) => {
+                return Err(Error::no_solution(format!("a packet was sent to {}", addr)))
+            }
+            Event::Quiescent => {
+                let (x, y) =
+                    nat.ok_or_else(|| Error::no_solution("idle with nothing for the NAT"))?;
+                if last_y == Some(y) {
+                    return Ok(y);
+                }
+                last_y = Some(y);
+                runtime.send(0, &[x, y]);
+            }
+            Event::Halted => return Err(Error::no_solution("the network halted")),
         }
     }
 }
 
-pub fn part2(machine: &Machine) -> i64 {
-    let mut network = Network::new(machine, NUM_MACHINES);
-    //let mut ys = HashSet::new();
-    loop {
-        network.step();
+pub struct Day23;
+impl Solution for Day23 {
+    type Input<'a> = Machine;
+
+    fn parse(input: &str) -> Result<Machine> {
+        Ok(Machine::from_string(input)?)
+    }
+    fn trace(machine: &mut Machine, tracer: &SharedTracer) {
+        machine.set_tracer(Some(tracer.clone()));
+    }
+    fn part1(machine: &Machine) -> Result<Answer> {
+        Ok(part1(machine)?.into())
+    }
+    fn part2(machine: &Machine) -> Result<Answer> {
+        Ok(part2(machine)?.into())
     }
 }
 
-pub fn start() {
-    let buffer = std::fs::read_to_string("./inputs/day23.txt").unwrap();
-    let machine = Machine::from_string(&buffer);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    println!("Program Output: {:?}", part2(&machine));
+    #[test]
+    fn nat() {
+        let buffer = std::fs::read_to_string("./inputs/day23.txt").unwrap();
+        let machine = Machine::from_string(&buffer).unwrap();
+        assert_eq!(part1(&machine).unwrap(), 22074);
+        assert_eq!(part2(&machine).unwrap(), 14257);
+    }
+
+    #[test]
+    fn schedulers() {
+        let buffer = std::fs::read_to_string("./inputs/day23.txt").unwrap();
+        let machine = Machine::from_string(&buffer).unwrap();
+        let mut network = Network::new(&machine, NUM_MACHINES, Scheduler::round_robin());
+        assert_eq!(network.run_until_nat().unwrap().1, 22074);
+        for scheduler in [
+            Scheduler::round_robin(),
+            Scheduler::event_driven(),
+            Scheduler::interleaved(23),
+        ] {
+            let mut network = Network::new(&machine, NUM_MACHINES, scheduler);
+            assert_eq!(network.run_nat().unwrap(), 14257);
+
+            let stats = network.stats();
+            assert_eq!(stats.len(), NUM_MACHINES);
+            let sent: usize = stats.iter().map(|stats| stats.sent).sum();
+            let received: usize = stats.iter().map(|stats| stats.received).sum();
+            assert!(received <= sent && received > 0);
+            assert!(stats.iter().all(|stats| stats.polls >= IDLE_POLLS));
+        }
+    }
 }
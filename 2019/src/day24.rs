@@ -1,4 +1,6 @@
-use crate::parsers::{byte, ParseResult};
+use crate::error::{self, Error};
+use crate::parsers::{byte, complete, ParseResult};
+use crate::solution::{Answer, Solution};
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 
@@ -8,15 +10,16 @@ enum Cell {
     Empty = 0,
 }
 impl Cell {
-    fn bug(s: &[u8]) -> ParseResult<Cell> {
+    fn bug(s: &[u8]) -> ParseResult<'_, Cell> {
         let (s, _) = byte(b'#')(s)?;
         Some((s, Cell::Bug))
     }
-    fn empty(s: &[u8]) -> ParseResult<Cell> {
+    fn empty(s: &[u8]) -> ParseResult<'_, Cell> {
         let (s, _) = byte(b'.')(s)?;
         Some((s, Cell::Empty))
     }
-    fn cell(s: &[u8]) -> ParseResult<Cell> {
+    #[allow(clippy::self_named_constructors)]
+    fn cell(s: &[u8]) -> ParseResult<'_, Cell> {
         Cell::bug(s).or_else(|| Cell::empty(s))
     }
 }
@@ -29,13 +32,8 @@ impl std::fmt::Display for Cell {
     }
 }
 
-#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Hash, PartialEq, Eq)]
 pub struct Eris(u32);
-impl Default for Eris {
-    fn default() -> Self {
-        Eris(0)
-    }
-}
 impl Eris {
     const WIDTH: i32 = 5;
     const HEIGHT: i32 = 5;
@@ -63,8 +61,8 @@ impl Eris {
         Cell::Bug
     }
     fn set(&mut self, x: i32, y: i32, cell: Cell) {
-        assert!(x < Eris::WIDTH && x >= 0);
-        assert!(y < Eris::HEIGHT && y >= 0);
+        assert!((0..Eris::WIDTH).contains(&x));
+        assert!((0..Eris::HEIGHT).contains(&y));
 
         match cell {
             Cell::Bug => self.0 |= 1 << Eris::index(x, y),
@@ -87,7 +85,8 @@ impl Eris {
         }
         next
     }
-    fn eris(mut s: &[u8]) -> ParseResult<Eris> {
+    #[allow(clippy::self_named_constructors)]
+    fn eris(mut s: &[u8]) -> ParseResult<'_, Eris> {
         let mut eris = Self::default();
         for y in 0..5 {
             for x in 0..5 {
@@ -223,7 +222,6 @@ impl ErisRec {
 pub fn part1(mut eris: Eris) -> u32 {
     let mut seen = HashSet::new();
     loop {
-        println!("{}", eris);
         if !seen.insert(eris) {
             break eris.value();
         }
@@ -239,10 +237,22 @@ pub fn part2(eris: Eris) -> usize {
     eris_rec.size()
 }
 
-pub fn start() {
-    let buffer = std::fs::read_to_string("./inputs/day24.txt").unwrap();
-    let eris = Eris::eris(buffer.as_bytes()).unwrap().1;
-    println!("Program Output: {:?}", part2(eris));
+pub struct Day24;
+impl Solution for Day24 {
+    type Input<'a> = Eris;
+
+    fn parse(input: &str) -> error::Result<Eris> {
+        complete(Eris::eris(input.as_bytes()), "a 5x5 grid of bugs")
+    }
+    fn part1(eris: &Eris) -> error::Result<Answer> {
+        Ok(part1(*eris).into())
+    }
+    fn part2(eris: &Eris) -> error::Result<Answer> {
+        if ErisRec::try_from(*eris).is_err() {
+            return Err(Error::parse("the centre tile must be empty"));
+        }
+        Ok(part2(*eris).into())
+    }
 }
 
 #[cfg(test)]
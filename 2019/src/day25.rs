@@ -1,12 +1,335 @@
-use crate::day05::Machine;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io;
 
-pub fn part1(mut machine: Machine) {
-    machine.terminal();
+use intcode::tracer::SharedTracer;
+use intcode::{Limit, Machine, StopReason};
+
+use crate::combinatorics::power_set;
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
+
+/// the airlock password is the only number in the text printed once the
+/// droid gets past the pressure sensitive floor.
+fn password(text: &str) -> Option<u64> {
+    let start = text.find("typing ")? + "typing ".len();
+    let digits: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// instructions a command may take before the droid is given up on, the
+/// usual commands take a few thousand.
+const COMMAND_LIMIT: usize = 1_000_000;
+
+const CHECKPOINT: &str = "Security Checkpoint";
+
+fn ascii(output: &[i64]) -> String {
+    output
+        .iter()
+        .filter_map(|c| u8::try_from(*c).ok())
+        .map(char::from)
+        .collect()
+}
+
+fn opposite(door: &str) -> Result<&'static str> {
+    match door {
+        "north" => Ok("south"),
+        "south" => Ok("north"),
+        "east" => Ok("west"),
+        "west" => Ok("east"),
+        _ => Err(Error::no_solution(format!(
+            "no way back through {:?}",
+            door
+        ))),
+    }
+}
+
+/// What the droid printed after a command.
+enum Reply {
+    Text(String),
+    Halted(String),
+    /// the command didn't finish.
+    Stuck,
+}
+
+fn send(machine: &mut Machine, command: &str) -> Result<Reply> {
+    let input = format!("{}\n", command);
+    let (output, reason) = machine.run_until(
+        input.bytes().map(i64::from),
+        Limit::Instructions(COMMAND_LIMIT),
+    )?;
+    Ok(match reason {
+        Some(StopReason::Halted) => Reply::Halted(ascii(&output)),
+        Some(_) => Reply::Text(ascii(&output)),
+        None => Reply::Stuck,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Room {
+    name: String,
+    doors: Vec<String>,
+    items: Vec<String>,
+}
+
+/// the `- ` list under `heading`.
+fn list(section: &str, heading: &str) -> Vec<String> {
+    let start = match section.find(heading) {
+        Some(start) => start + heading.len(),
+        None => return Vec::new(),
+    };
+    section[start..]
+        .lines()
+        .skip_while(|line| line.is_empty())
+        .map_while(|line| line.strip_prefix("- "))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The room the droid ended up in, the last one described if the droid was
+/// thrown out of one.
+fn parse_room(text: &str) -> Option<Room> {
+    let start = text.rfind("== ")?;
+    let section = &text[start + "== ".len()..];
+    let name = &section[..section.find(" ==")?];
+    Some(Room {
+        name: name.to_string(),
+        doors: list(section, "Doors here lead:"),
+        items: list(section, "Items here:"),
+    })
+}
+
+/// Maps the ship, picking up everything that's safe to carry.
+struct Bot {
+    machine: Machine,
+    /// the doors from the hull breach to each room.
+    paths: HashMap<String, Vec<String>>,
+    /// the checkpoint door onto the pressure sensitive floor.
+    floor: Option<String>,
+    /// the items picked up.
+    held: Vec<String>,
+    /// items that ended the game or stopped the droid.
+    deadly: Vec<String>,
+    password: Option<u64>,
 }
+impl Bot {
+    fn go(&mut self, door: &str) -> Result<Option<Room>> {
+        match send(&mut self.machine, door)? {
+            Reply::Text(text) => Ok(parse_room(&text)),
+            Reply::Halted(text) => {
+                self.password = password(&text);
+                Ok(None)
+            }
+            Reply::Stuck => Err(Error::no_solution(format!(
+                "the droid got stuck going {}",
+                door
+            ))),
+        }
+    }
+
+    /// whether the droid can still move after taking `item`, tried on a
+    /// copy of the droid.
+    fn is_safe(&self, item: &str, door: &str) -> Result<bool> {
+        let mut probe = self.machine.clone();
+        match send(&mut probe, &format!("take {}", item))? {
+            Reply::Text(_) => {}
+            Reply::Halted(_) | Reply::Stuck => return Ok(false),
+        }
+        match send(&mut probe, door)? {
+            Reply::Text(text) => Ok(parse_room(&text).is_some()),
+            Reply::Halted(_) | Reply::Stuck => Ok(false),
+        }
+    }
+
+    fn take(&mut self, item: &str) -> Result<()> {
+        match send(&mut self.machine, &format!("take {}", item))? {
+            Reply::Text(_) => {
+                self.held.push(item.to_string());
+                Ok(())
+            }
+            _ => Err(Error::no_solution(format!(
+                "taking the {} went wrong",
+                item
+            ))),
+        }
+    }
+
+    /// Depth first through every door, returning to `room` afterwards.
+    /// Stops early if the droid got past the floor.
+    fn explore(&mut self, room: Room, path: &mut Vec<String>) -> Result<()> {
+        self.paths.insert(room.name.clone(), path.clone());
+        for item in room.items.iter() {
+            let door = room
+                .doors
+                .first()
+                .ok_or_else(|| Error::no_solution(format!("no way out of {}", room.name)))?;
+            if self.is_safe(item, door)? {
+                self.take(item)?;
+            } else {
+                self.deadly.push(item.clone());
+            }
+        }
+
+        let back = path.last().map(|door| opposite(door)).transpose()?;
+        for door in room
+            .doors
+            .iter()
+            .filter(|&door| Some(door.as_str()) != back)
+        {
+            let next = match self.go(door)? {
+                Some(next) => next,
+                None if self.password.is_some() => return Ok(()),
+                None => return Err(Error::no_solution(format!("nothing past {}", door))),
+            };
+            if next.name == room.name {
+                // thrown back from the floor.
+                self.floor = Some(door.clone());
+                continue;
+            }
+            if !self.paths.contains_key(&next.name) {
+                path.push(door.clone());
+                self.explore(next, path)?;
+                path.pop();
+                if self.password.is_some() {
+                    return Ok(());
+                }
+            }
+            self.go(opposite(door)?)?;
+        }
+        Ok(())
+    }
+
+    /// Stand on the floor with every combination of the items held, one
+    /// item taken or dropped at a time, until it lets the droid through.
+    fn weigh(&mut self, floor: &str) -> Result<()> {
+        let mut holding = self.held.clone();
+        for subset in power_set(&self.held)? {
+            let drop = holding.iter().filter(|item| !subset.contains(item));
+            let take = subset.iter().filter(|item| !holding.contains(item));
+            let commands: Vec<_> = drop
+                .map(|item| format!("drop {}", item))
+                .chain(take.map(|item| format!("take {}", item)))
+                .collect();
+            for command in commands {
+                match send(&mut self.machine, &command)? {
+                    Reply::Text(_) => {}
+                    _ => return Err(Error::no_solution(format!("couldn't {}", command))),
+                }
+            }
+            holding = subset;
+
+            self.go(floor)?;
+            if self.password.is_some() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Explore the ship picking up the safe items, then find the ones that get
+/// the droid past the pressure sensitive floor.
+pub fn part1(machine: Machine) -> Result<Option<u64>> {
+    Ok(solve(machine)?.password)
+}
+
+fn solve(mut machine: Machine) -> Result<Bot> {
+    let start = parse_room(&ascii(&machine.run(std::iter::empty())?))
+        .ok_or_else(|| Error::no_solution("the droid isn't in a room"))?;
+    let mut bot = Bot {
+        machine,
+        paths: HashMap::new(),
+        floor: None,
+        held: Vec::new(),
+        deadly: Vec::new(),
+        password: None,
+    };
+    bot.explore(start, &mut Vec::new())?;
+    if bot.password.is_some() {
+        return Ok(bot);
+    }
+
+    let path = bot
+        .paths
+        .get(CHECKPOINT)
+        .cloned()
+        .ok_or_else(|| Error::no_solution("no security checkpoint"))?;
+    let floor = bot
+        .floor
+        .clone()
+        .ok_or_else(|| Error::no_solution("no pressure sensitive floor"))?;
+    for door in path.iter() {
+        bot.go(door)?;
+    }
+    bot.weigh(&floor)?;
+    Ok(bot)
+}
+
+/// play the adventure by hand.
+pub fn play_by_hand(mut machine: Machine) -> Result<Option<u64>> {
+    let transcript = machine.terminal(io::stdin().lock(), io::stdout())?;
+    Ok(password(&ascii(&transcript)))
+}
+
+pub struct Day25;
+impl Solution for Day25 {
+    type Input<'a> = Machine;
+
+    const HAS_PART2: bool = false;
+
+    fn parse(input: &str) -> Result<Machine> {
+        Ok(Machine::from_string(input)?)
+    }
+    fn trace(machine: &mut Machine, tracer: &SharedTracer) {
+        machine.set_tracer(Some(tracer.clone()));
+    }
+    fn part1(machine: &Machine) -> Result<Answer> {
+        let password = part1(machine.clone())?;
+        Ok(password
+            .ok_or_else(|| Error::no_solution("no password found"))?
+            .into())
+    }
+    fn part2(_machine: &Machine) -> Result<Answer> {
+        Err(Error::no_solution("day 25 only has one part"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-pub fn start() {
-    let buffer = std::fs::read_to_string("./inputs/day25.txt").unwrap();
-    let machine = Machine::from_string(&buffer);
+    #[test]
+    fn rooms() {
+        let text = "\n\n\n== Hallway ==\nThis area has been optimized for something.\n\n\
+                    Doors here lead:\n- north\n- west\n\nItems here:\n- boulder\n\nCommand?\n";
+        let room = parse_room(text).unwrap();
+        assert_eq!(room.name, "Hallway");
+        assert_eq!(room.doors, vec!["north", "west"]);
+        assert_eq!(room.items, vec!["boulder"]);
+        assert_eq!(opposite("west").unwrap(), "east");
+        assert!(opposite("up").is_err());
+    }
 
-    println!("Program Output: {:?}", part1(machine));
+    #[test]
+    fn bot() {
+        let buffer = std::fs::read_to_string("./inputs/day25.txt").unwrap();
+        let machine = Machine::from_string(&buffer).unwrap();
+        let bot = solve(machine).unwrap();
+        assert_eq!(bot.password, Some(33624080));
+        assert_eq!(bot.paths.len(), 19);
+        assert_eq!(bot.held.len(), 8);
+        let mut deadly = bot.deadly;
+        deadly.sort();
+        let expected = [
+            "escape pod",
+            "giant electromagnet",
+            "infinite loop",
+            "molten lava",
+            "photons",
+        ];
+        assert_eq!(deadly, expected);
+    }
 }
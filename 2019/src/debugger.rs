@@ -0,0 +1,398 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::convert::TryFrom;
+use std::io::{BufRead, Write};
+
+use intcode::disassembler::decode_with;
+use intcode::{Machine, StopReason};
+
+use crate::error::Result;
+
+const HELP: &str = "\
+s, step [n]          execute n instructions (default 1)
+c, continue          run until a breakpoint, watchpoint, halt or input is needed
+o, out [n]           run until n values are output (default 1)
+b, break [addr]      set a breakpoint on pc, or list breakpoints
+w, watch [addr]      stop when a memory cell changes, or list watchpoints
+d, delete <addr>     remove a breakpoint or watchpoint
+r, regs              show pc and sp
+x <addr> [n]         show n memory cells (default 1)
+p, poke <addr> <v>   write v to memory
+l, list [n]          disassemble n instructions from pc (default 5)
+i, in <v>...         queue input values
+t, text <line>       queue a line of ASCII input
+q, quit              leave the debugger";
+
+/// `x` and `poke` stay below this address, so that a typo can't grow
+/// memory until it runs out.
+const MAX_ADDRESS: usize = 1 << 24;
+
+/// Why the machine stopped under the debugger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Stepped,
+    Breakpoint(usize),
+    Watchpoint { addr: usize, old: i64, new: i64 },
+    Outputs,
+    Stopped(StopReason),
+}
+
+/// An interactive debugger wrapped around a machine.
+pub struct Debugger {
+    machine: Machine,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeSet<usize>,
+    input: VecDeque<i64>,
+    output: Vec<i64>,
+}
+impl Debugger {
+    pub fn new(machine: Machine) -> Self {
+        Debugger {
+            machine,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+            input: VecDeque::new(),
+            output: Vec::new(),
+        }
+    }
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+    /// everything the machine has output so far.
+    pub fn output(&self) -> &[i64] {
+        &self.output
+    }
+    pub fn add_breakpoint(&mut self, pc: usize) {
+        self.breakpoints.insert(pc);
+    }
+    pub fn add_watchpoint(&mut self, addr: usize) {
+        self.watchpoints.insert(addr);
+    }
+    pub fn push_input(&mut self, value: i64) {
+        self.input.push_back(value);
+    }
+
+    /// execute one instruction, returning why the debugger should stop if it
+    /// should.
+    fn step(&mut self) -> Result<Option<Event>> {
+        let before: Vec<_> = self
+            .watchpoints
+            .iter()
+            .map(|&addr| self.machine.mem_get(addr))
+            .collect();
+
+        let input = &mut self.input;
+        let stop = self
+            .machine
+            .step(std::iter::from_fn(|| input.pop_front()))?;
+
+        for (&addr, old) in self.watchpoints.iter().zip(before) {
+            let new = self.machine.mem_get(addr);
+            if new != old {
+                return Ok(Some(Event::Watchpoint { addr, old, new }));
+            }
+        }
+        match stop {
+            Some(StopReason::Output(value)) => {
+                self.output.push(value);
+                Ok(None)
+            }
+            Some(reason) => Ok(Some(Event::Stopped(reason))),
+            None => Ok(None),
+        }
+    }
+
+    /// execute up to `count` instructions.
+    pub fn step_n(&mut self, count: usize) -> Result<Event> {
+        for _ in 0..count {
+            if let Some(event) = self.step()? {
+                return Ok(event);
+            }
+        }
+        Ok(Event::Stepped)
+    }
+
+    /// run until something interesting happens. A breakpoint on the current
+    /// pc doesn't stop the machine again.
+    pub fn continue_(&mut self) -> Result<Event> {
+        loop {
+            if let Some(event) = self.step()? {
+                return Ok(event);
+            }
+            if self.breakpoints.contains(&self.machine.pc()) {
+                return Ok(Event::Breakpoint(self.machine.pc()));
+            }
+        }
+    }
+
+    /// run until `count` more values have been output.
+    pub fn run_until_output(&mut self, count: usize) -> Result<Event> {
+        let target = self.output.len() + count;
+        while self.output.len() < target {
+            if let Some(event) = self.step()? {
+                return Ok(event);
+            }
+            if self.breakpoints.contains(&self.machine.pc()) {
+                return Ok(Event::Breakpoint(self.machine.pc()));
+            }
+        }
+        Ok(Event::Outputs)
+    }
+
+    fn print_instruction<W: Write>(&self, out: &mut W, pc: usize) -> Result<usize> {
+        let marker = if pc == self.machine.pc() { '>' } else { ' ' };
+        match decode_with(|addr| Some(self.machine.mem_get(addr)), pc) {
+            Some(instruction) => {
+                writeln!(out, "{}{:5}: {}", marker, pc, instruction)?;
+                Ok(instruction.size())
+            }
+            None => {
+                writeln!(out, "{}{:5}: data {}", marker, pc, self.machine.mem_get(pc))?;
+                Ok(1)
+            }
+        }
+    }
+
+    fn print_event<W: Write>(&self, out: &mut W, event: Event, printed: usize) -> Result<()> {
+        let mut line_open = false;
+        for &value in self.output[printed..].iter() {
+            match u8::try_from(value) {
+                Ok(c) if c.is_ascii() => {
+                    write!(out, "{}", char::from(c))?;
+                    line_open = c != b'\n';
+                }
+                _ => {
+                    writeln!(out, "{}", value)?;
+                    line_open = false;
+                }
+            }
+        }
+        if line_open {
+            writeln!(out)?;
+        }
+        match event {
+            Event::Stepped | Event::Outputs => {}
+            Event::Breakpoint(pc) => writeln!(out, "breakpoint at {}", pc)?,
+            Event::Watchpoint { addr, old, new } => writeln!(
+                out,
+                "watchpoint: [{}] changed from {} to {}",
+                addr, old, new
+            )?,
+            Event::Stopped(StopReason::Halted) => writeln!(out, "halted")?,
+            Event::Stopped(StopReason::NeedsInput) => writeln!(out, "waiting for input")?,
+            Event::Stopped(StopReason::Output(_)) => unreachable!(),
+        }
+        self.print_instruction(out, self.machine.pc())?;
+        Ok(())
+    }
+
+    /// Run a single command, returns false when the user quits.
+    pub fn command<W: Write>(&mut self, line: &str, out: &mut W) -> Result<bool> {
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => return Ok(true),
+        };
+        let args: Vec<&str> = words.collect();
+        let numbers: Option<Vec<i64>> = args.iter().map(|arg| arg.parse().ok()).collect();
+        let numbers = match numbers {
+            Some(numbers) => numbers,
+            None if matches!(command, "t" | "text") => Vec::new(),
+            None => {
+                writeln!(out, "expected numbers, found {:?}", args.join(" "))?;
+                return Ok(true);
+            }
+        };
+        let addr = |index: usize| numbers.get(index).and_then(|&n| usize::try_from(n).ok());
+        let count = |default: usize| addr(0).unwrap_or(default);
+
+        let printed = self.output.len();
+        let result = match command {
+            "s" | "step" => self.step_n(count(1)),
+            "c" | "continue" => self.continue_(),
+            "o" | "out" => self.run_until_output(count(1)),
+            "b" | "break" | "w" | "watch" => {
+                let set = if command.starts_with('b') {
+                    &mut self.breakpoints
+                } else {
+                    &mut self.watchpoints
+                };
+                match addr(0) {
+                    Some(addr) => {
+                        set.insert(addr);
+                    }
+                    None => {
+                        let addrs: Vec<_> = set.iter().map(usize::to_string).collect();
+                        writeln!(out, "{}", addrs.join(" "))?;
+                    }
+                }
+                return Ok(true);
+            }
+            "d" | "delete" => {
+                if let Some(addr) = addr(0) {
+                    self.breakpoints.remove(&addr);
+                    self.watchpoints.remove(&addr);
+                }
+                return Ok(true);
+            }
+            "r" | "regs" => {
+                writeln!(out, "pc {} sp {}", self.machine.pc(), self.machine.sp())?;
+                return Ok(true);
+            }
+            "x" => {
+                if let Some(start) = addr(0) {
+                    match start.checked_add(addr(1).unwrap_or(1)) {
+                        Some(end) if end <= MAX_ADDRESS => {
+                            let cells: Vec<_> = (start..end)
+                                .map(|addr| self.machine.mem_get(addr).to_string())
+                                .collect();
+                            writeln!(out, "{:5}: {}", start, cells.join(" "))?;
+                        }
+                        _ => writeln!(out, "addresses stop at {}", MAX_ADDRESS)?,
+                    }
+                }
+                return Ok(true);
+            }
+            "p" | "poke" => {
+                match (addr(0), numbers.get(1)) {
+                    (Some(addr), Some(&value)) if addr < MAX_ADDRESS => {
+                        self.machine.mem_set(addr, value)
+                    }
+                    (Some(_), Some(_)) => writeln!(out, "addresses stop at {}", MAX_ADDRESS)?,
+                    _ => writeln!(out, "usage: poke <addr> <value>")?,
+                }
+                return Ok(true);
+            }
+            "l" | "list" => {
+                let mut pc = self.machine.pc();
+                for _ in 0..count(5) {
+                    pc += self.print_instruction(out, pc)?;
+                }
+                return Ok(true);
+            }
+            "i" | "in" => {
+                self.input.extend(numbers);
+                return Ok(true);
+            }
+            "t" | "text" => {
+                let text = line.trim_start()[command.len()..].trim();
+                self.input
+                    .extend(text.bytes().chain(Some(b'\n')).map(i64::from));
+                return Ok(true);
+            }
+            "h" | "help" => {
+                writeln!(out, "{}", HELP)?;
+                return Ok(true);
+            }
+            "q" | "quit" => return Ok(false),
+            _ => {
+                writeln!(out, "unknown command {:?}, try help", command)?;
+                return Ok(true);
+            }
+        };
+        match result {
+            Ok(event) => self.print_event(out, event, printed)?,
+            Err(err) => writeln!(out, "{}", err)?,
+        }
+        Ok(true)
+    }
+
+    /// run the REPL until the user quits or `input` runs out.
+    pub fn repl<R: BufRead, W: Write>(&mut self, input: R, mut out: W) -> Result<()> {
+        self.print_instruction(&mut out, self.machine.pc())?;
+        write!(out, "(icdb) ")?;
+        out.flush()?;
+        for line in input.lines() {
+            if !self.command(&line?, &mut out)? {
+                break;
+            }
+            write!(out, "(icdb) ")?;
+            out.flush()?;
+        }
+        writeln!(out)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode::assembler::assemble;
+
+    fn countdown() -> Debugger {
+        let program = "
+            loop: out [count]
+                  add [count], #-1, [count]
+                  jnz [count], #loop
+                  hlt
+            count: data 3
+        ";
+        Debugger::new(Machine::new(assemble(program).unwrap()))
+    }
+
+    #[test]
+    fn breakpoints() {
+        let mut debugger = countdown();
+        debugger.add_breakpoint(6);
+        assert_eq!(debugger.continue_().unwrap(), Event::Breakpoint(6));
+        assert_eq!(debugger.continue_().unwrap(), Event::Breakpoint(6));
+        assert_eq!(debugger.output(), &[3, 2]);
+    }
+
+    #[test]
+    fn watchpoints() {
+        let mut debugger = countdown();
+        debugger.add_watchpoint(10);
+        let event = debugger.continue_().unwrap();
+        assert_eq!(
+            event,
+            Event::Watchpoint {
+                addr: 10,
+                old: 3,
+                new: 2
+            }
+        );
+        assert_eq!(debugger.machine().pc(), 6);
+    }
+
+    #[test]
+    fn commands() {
+        let mut debugger = countdown();
+        let mut out = Vec::new();
+        for command in ["poke 10 5", "o 2", "s 2", "r", "c"].iter() {
+            debugger.command(command, &mut out).unwrap();
+        }
+        assert_eq!(debugger.output(), &[5, 4, 3, 2, 1]);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("pc 0 sp 0"), "{}", out);
+        assert!(out.ends_with("halted\n>    9: hlt\n"), "{}", out);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let mut debugger = countdown();
+        let mut out = Vec::new();
+        let huge = i64::MAX.to_string();
+        for command in [format!("x 1 {}", huge), format!("poke {} 1", huge)].iter() {
+            debugger.command(command, &mut out).unwrap();
+        }
+        assert_eq!(debugger.machine().mem_size(), 11);
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches("addresses stop at").count(), 2, "{}", out);
+    }
+
+    #[test]
+    fn input() {
+        let program = assemble("in [value]\nhlt\nvalue: data 0").unwrap();
+        let mut debugger = Debugger::new(Machine::new(program));
+        assert_eq!(
+            debugger.continue_().unwrap(),
+            Event::Stopped(StopReason::NeedsInput)
+        );
+        debugger.push_input(42);
+        assert_eq!(
+            debugger.continue_().unwrap(),
+            Event::Stopped(StopReason::Halted)
+        );
+        assert_eq!(debugger.machine().mem_get(3), 42);
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::VecDeque;
+
+use ncurses as nc;
+
+use crate::error::{Error, Result};
+
+/// A key the games understand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Char(char),
+}
+
+/// Somewhere to draw a game, in character cells with `(0, 0)` top left.
+pub trait Display {
+    fn put(&mut self, x: i32, y: i32, c: char);
+    /// write `text` starting at `(x, y)`, on a single line.
+    fn print(&mut self, x: i32, y: i32, text: &str) {
+        for (c, x) in text.chars().zip(x..) {
+            self.put(x, y, c);
+        }
+    }
+    fn clear(&mut self);
+    /// show everything drawn since the last refresh.
+    fn refresh(&mut self) {}
+}
+
+/// Where a game's keys come from.
+pub trait InputSource {
+    /// the next key, or `None` once there are no more.
+    fn key(&mut self) -> Option<Key>;
+}
+
+/// The terminal, through ncurses. Only one should exist at a time, the
+/// terminal is restored when it's dropped.
+pub struct Curses(());
+impl Curses {
+    /// Take over the terminal.
+    pub fn init() -> Self {
+        nc::initscr();
+        nc::cbreak();
+        nc::keypad(nc::stdscr(), true);
+        nc::noecho();
+        Curses(())
+    }
+}
+impl Drop for Curses {
+    fn drop(&mut self) {
+        nc::endwin();
+    }
+}
+impl Display for Curses {
+    fn put(&mut self, x: i32, y: i32, c: char) {
+        nc::mvaddch(y, x, c.into());
+    }
+    fn print(&mut self, x: i32, y: i32, text: &str) {
+        nc::mvprintw(y, x, text);
+    }
+    fn clear(&mut self) {
+        nc::clear();
+    }
+    fn refresh(&mut self) {
+        nc::refresh();
+    }
+}
+
+/// Keys typed into the terminal, only while a `Curses` is up.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Keyboard;
+impl InputSource for Keyboard {
+    /// keys other than the arrows and plain characters are skipped.
+    fn key(&mut self) -> Option<Key> {
+        loop {
+            let key = match nc::getch() {
+                nc::ERR => return None,
+                nc::KEY_UP => Key::Up,
+                nc::KEY_DOWN => Key::Down,
+                nc::KEY_LEFT => Key::Left,
+                nc::KEY_RIGHT => Key::Right,
+                code => match std::char::from_u32(code as u32) {
+                    Some(c) if c.is_ascii() => Key::Char(c),
+                    _ => continue,
+                },
+            };
+            return Some(key);
+        }
+    }
+}
+
+/// A screen in memory, for running games without a terminal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Framebuffer {
+    rows: Vec<Vec<char>>,
+}
+impl Framebuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn get(&self, x: i32, y: i32) -> char {
+        if x < 0 || y < 0 {
+            return ' ';
+        }
+        self.rows
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .copied()
+            .unwrap_or(' ')
+    }
+    /// the screen as text, without trailing spaces or blank lines.
+    pub fn snapshot(&self) -> String {
+        let mut lines: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim_end().to_string())
+            .collect();
+        while matches!(lines.last(), Some(line) if line.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+}
+impl Display for Framebuffer {
+    /// cells off the top or left of the screen are dropped.
+    fn put(&mut self, x: i32, y: i32, c: char) {
+        if x < 0 || y < 0 {
+            return;
+        }
+        let (x, y) = (x as usize, y as usize);
+        if self.rows.len() <= y {
+            self.rows.resize(y + 1, Vec::new());
+        }
+        let row = &mut self.rows[y];
+        if row.len() <= x {
+            row.resize(x + 1, ' ');
+        }
+        row[x] = c;
+    }
+    fn clear(&mut self) {
+        self.rows.clear();
+    }
+}
+
+/// A fixed sequence of keys, to replay a recorded session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Script {
+    keys: VecDeque<Key>,
+}
+impl Script {
+    pub fn new<I: IntoIterator<Item = Key>>(keys: I) -> Self {
+        Script {
+            keys: keys.into_iter().collect(),
+        }
+    }
+    /// Parse a recording, keys separated by whitespace. The arrows are `up`,
+    /// `down`, `left` and `right`, `space` is a space, and any other single
+    /// character is itself. `#` starts a comment.
+    pub fn parse(recording: &str) -> Result<Self> {
+        let mut keys = VecDeque::new();
+        for line in recording.lines() {
+            let line = line.split('#').next().unwrap_or("");
+            for word in line.split_whitespace() {
+                let key = match word {
+                    "up" => Key::Up,
+                    "down" => Key::Down,
+                    "left" => Key::Left,
+                    "right" => Key::Right,
+                    "space" => Key::Char(' '),
+                    _ => {
+                        let mut chars = word.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) => Key::Char(c),
+                            _ => return Err(Error::parse(format!("unknown key {:?}", word))),
+                        }
+                    }
+                };
+                keys.push_back(key);
+            }
+        }
+        Ok(Script { keys })
+    }
+    /// how many keys are left.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+impl InputSource for Script {
+    fn key(&mut self) -> Option<Key> {
+        self.keys.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn framebuffer() {
+        let mut screen = Framebuffer::new();
+        screen.put(2, 1, '#');
+        screen.print(0, 3, "Score: 5");
+        screen.put(-1, 0, 'x');
+        assert_eq!(screen.get(2, 1), '#');
+        assert_eq!(screen.snapshot(), "\n  #\n\nScore: 5");
+        screen.print(0, 3, "   ");
+        assert_eq!(screen.snapshot(), "\n  #\n\n   re: 5");
+        screen.clear();
+        assert_eq!(screen.snapshot(), "");
+    }
+
+    #[test]
+    fn script() {
+        let mut script = Script::parse("left left # dodge\nspace q").unwrap();
+        assert_eq!(script.len(), 4);
+        let keys: Vec<_> = std::iter::from_fn(|| script.key()).collect();
+        assert_eq!(
+            keys,
+            vec![Key::Left, Key::Left, Key::Char(' '), Key::Char('q')]
+        );
+        assert!(Script::parse("jump").is_err());
+    }
+}
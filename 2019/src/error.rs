@@ -0,0 +1,80 @@
+use std::fmt;
+use std::io;
+
+pub use intcode::Fault;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The puzzle input was malformed.
+    Parse(String),
+    /// An Intcode program did something illegal. `pc` is the address of the
+    /// faulting instruction.
+    Machine {
+        pc: usize,
+        opcode: i64,
+        fault: Fault,
+    },
+    /// The puzzle logic ran to completion without finding an answer.
+    NoSolution(String),
+    Io(io::Error),
+    Save(serde_json::Error),
+    /// A save couldn't be used, or the slot name was invalid.
+    Store(String),
+}
+impl Error {
+    pub fn parse<S: Into<String>>(message: S) -> Self {
+        Error::Parse(message.into())
+    }
+    pub fn no_solution<S: Into<String>>(message: S) -> Self {
+        Error::NoSolution(message.into())
+    }
+    pub fn store<S: Into<String>>(message: S) -> Self {
+        Error::Store(message.into())
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(message) => write!(f, "parse error: {}", message),
+            Error::Machine { pc, opcode, fault } => {
+                write!(f, "{} at pc {} (opcode {})", fault, pc, opcode)
+            }
+            Error::NoSolution(message) => write!(f, "no solution: {}", message),
+            Error::Io(err) => write!(f, "io error: {}", err),
+            Error::Save(err) => write!(f, "invalid save file: {}", err),
+            Error::Store(message) => write!(f, "save store: {}", message),
+        }
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Save(err) => Some(err),
+            Error::Parse(_) | Error::Machine { .. } | Error::NoSolution(_) | Error::Store(_) => {
+                None
+            }
+        }
+    }
+}
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+impl From<intcode::Error> for Error {
+    fn from(err: intcode::Error) -> Self {
+        match err {
+            intcode::Error::Parse(message) => Error::Parse(message),
+            intcode::Error::Machine { pc, opcode, fault } => Error::Machine { pc, opcode, fault },
+            intcode::Error::Io(err) => Error::Io(err),
+        }
+    }
+}
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Save(err)
+    }
+}
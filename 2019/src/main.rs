@@ -1,31 +1,278 @@
-pub mod parsers;
-
-pub mod day01;
-pub mod day02;
-pub mod day03;
-pub mod day04;
-pub mod day05;
-pub mod day06;
-pub mod day07;
-pub mod day08;
-pub mod day09;
-pub mod day10;
-pub mod day11;
-pub mod day12;
-pub mod day13;
-pub mod day14;
-pub mod day15;
-pub mod day16;
-pub mod day17;
-pub mod day18;
-pub mod day19;
-pub mod day20;
-pub mod day21;
-pub mod day22;
-pub mod day23;
-pub mod day24;
-pub mod day25;
+use std::io::Read;
+
+use advent_of_code::{day13, day15, day17, day21, day25, debugger, registry, saves};
+use intcode::{disassembler, tracer, Machine};
+
+const USAGE: &str = "\
+usage: advent-of-code <year> <day> [--part <1|2>] [--input <path>]
+                     [--disassemble | --debug | --play]
+                     [--profile] [--trace <path>] [--saves <dir>]
+       advent-of-code [--saves <dir>] --list-saves | --delete-save <slot>
+
+  --part          run only the given part, both parts are run by default
+  --input         read the puzzle input from a file, or from stdin with `-`.
+                  defaults to ./inputs/dayNN.txt
+  --disassemble   list an Intcode input as code instead of solving it
+  --debug         step through an Intcode input in the debugger
+  --play          play day 13, 15, 17, 21 or 25 by hand instead of solving it
+  --profile       count the Intcode instructions executed while solving
+  --trace         also log every Intcode instruction executed to a file
+  --saves         keep saved games in a directory, defaults to ./saves
+  --list-saves    list the saved games
+  --delete-save   delete a saved game";
+
+enum InputSource {
+    Default,
+    Stdin,
+    File(String),
+}
+
+enum SaveCommand {
+    List,
+    Delete(String),
+}
+
+struct Args {
+    year: u32,
+    day: u32,
+    part: Option<u32>,
+    input: InputSource,
+    disassemble: bool,
+    debug: bool,
+    play: bool,
+    profile: bool,
+    trace: Option<String>,
+    saves: Option<String>,
+    save_command: Option<SaveCommand>,
+}
+impl Args {
+    fn parse<I>(mut args: I) -> Result<Self, String>
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut positional = Vec::new();
+        let mut part = None;
+        let mut input = InputSource::Default;
+        let mut disassemble = false;
+        let mut debug = false;
+        let mut play = false;
+        let mut profile = false;
+        let mut trace = None;
+        let mut saves = None;
+        let mut save_command = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--part" | "-p" => {
+                    let value = args.next().ok_or("--part requires a value")?;
+                    part = match value.as_str() {
+                        "1" => Some(1),
+                        "2" => Some(2),
+                        _ => return Err(format!("invalid part: {}", value)),
+                    };
+                }
+                "--input" | "-i" => {
+                    let value = args.next().ok_or("--input requires a value")?;
+                    input = match value.as_str() {
+                        "-" => InputSource::Stdin,
+                        _ => InputSource::File(value),
+                    };
+                }
+                "--disassemble" | "-d" => disassemble = true,
+                "--debug" => debug = true,
+                "--play" => play = true,
+                "--profile" => profile = true,
+                "--trace" => {
+                    trace = Some(args.next().ok_or("--trace requires a value")?);
+                }
+                "--saves" => {
+                    saves = Some(args.next().ok_or("--saves requires a value")?);
+                }
+                "--list-saves" => save_command = Some(SaveCommand::List),
+                "--delete-save" => {
+                    let slot = args.next().ok_or("--delete-save requires a value")?;
+                    save_command = Some(SaveCommand::Delete(slot));
+                }
+                "--help" | "-h" => return Err(String::new()),
+                _ => positional.push(arg),
+            }
+        }
+
+        let (year, day) = match positional.as_slice() {
+            [year, day] => (year.as_str(), day.as_str()),
+            [] if save_command.is_some() => ("0", "0"),
+            _ => return Err("expected a year and a day".to_string()),
+        };
+        let year = year
+            .parse()
+            .map_err(|_| format!("invalid year: {}", year))?;
+        let day = day.parse().map_err(|_| format!("invalid day: {}", day))?;
+
+        Ok(Args {
+            year,
+            day,
+            part,
+            input,
+            disassemble,
+            debug,
+            play,
+            profile,
+            trace,
+            saves,
+            save_command,
+        })
+    }
+}
+
+fn read_input(source: &InputSource, puzzle: &registry::Puzzle) -> Result<String, String> {
+    match source {
+        InputSource::Stdin => {
+            let mut buffer = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buffer)
+                .map_err(|err| format!("could not read stdin: {}", err))?;
+            Ok(buffer)
+        }
+        InputSource::File(path) => {
+            std::fs::read_to_string(path).map_err(|err| format!("could not read {}: {}", path, err))
+        }
+        InputSource::Default => {
+            let path = puzzle.default_input();
+            std::fs::read_to_string(&path)
+                .map_err(|err| format!("could not read {}: {}", path, err))
+        }
+    }
+}
+
+fn run_save_command(store: &saves::SaveStore, command: &SaveCommand) -> Result<(), String> {
+    match command {
+        SaveCommand::List => {
+            let list = store.list().map_err(|err| err.to_string())?;
+            for (slot, err) in list.unreadable {
+                eprintln!("{}: {}", slot, err);
+            }
+            for save in list.saves {
+                let mut line = format!("{:20} version {}", save.slot, save.version);
+                if save.saved_at != 0 {
+                    line.push_str(&format!(", saved at {}", save.saved_at));
+                }
+                if let Some(day) = save.metadata.day {
+                    line.push_str(&format!(", day {}", day));
+                }
+                if let Some(score) = save.metadata.score {
+                    line.push_str(&format!(", score {}", score));
+                }
+                println!("{}", line);
+            }
+        }
+        SaveCommand::Delete(slot) => store.delete(slot).map_err(|err| err.to_string())?,
+    }
+    Ok(())
+}
+
+/// play one of the games in the terminal.
+fn play(day: u32, machine: Machine, store: &saves::SaveStore) -> Result<(), String> {
+    let result = match day {
+        13 => day13::play_by_hand(machine, store).map(|score| format!("score {}", score)),
+        15 => day15::explore_by_hand(machine).map(|(distance, furthest)| match distance {
+            Some(distance) => format!(
+                "oxygen system {} moves away, at most {} from it",
+                distance, furthest
+            ),
+            None => "oxygen system not found".to_string(),
+        }),
+        17 => day17::play_by_hand(machine).map(|dust| match dust {
+            Some(dust) => format!("{} dust collected", dust),
+            None => "the robot didn't accept the routines".to_string(),
+        }),
+        21 => day21::play_by_hand(machine).map(|damage| match damage {
+            Some(damage) => format!("hull damage {}", damage),
+            None => "the droid fell into space".to_string(),
+        }),
+        25 => day25::play_by_hand(machine).map(|password| match password {
+            Some(password) => format!("password {}", password),
+            None => "no password".to_string(),
+        }),
+        _ => return Err(format!("day {} can't be played by hand", day)),
+    };
+    println!("{}", result.map_err(|err| err.to_string())?);
+    Ok(())
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let store = saves::SaveStore::new(args.saves.as_deref().unwrap_or(saves::DEFAULT_DIRECTORY));
+    if let Some(command) = &args.save_command {
+        return run_save_command(&store, command);
+    }
+    if args.year != registry::YEAR {
+        return Err(format!("no solutions for year {}", args.year));
+    }
+    let puzzle =
+        registry::find(args.day).ok_or_else(|| format!("no solution for day {}", args.day))?;
+    let input = read_input(&args.input, puzzle)?;
+
+    if args.disassemble || args.debug || args.play {
+        let machine =
+            Machine::from_string(&input).map_err(|err| format!("day {}: {}", puzzle.day, err))?;
+        if args.disassemble {
+            print!("{}", disassembler::disassemble(&machine.memory()));
+        } else if args.play {
+            play(puzzle.day, machine, &store)?;
+        } else {
+            let stdin = std::io::stdin();
+            debugger::Debugger::new(machine)
+                .repl(stdin.lock(), std::io::stdout())
+                .map_err(|err| err.to_string())?;
+        }
+        return Ok(());
+    }
+
+    let tracer = match &args.trace {
+        Some(path) => Some(tracer::Tracer::to_file(path).map_err(|err| err.to_string())?),
+        None if args.profile => Some(tracer::Tracer::new()),
+        None => None,
+    };
+    let tracer = tracer.map(tracer::Tracer::shared);
+
+    let parts = match args.part {
+        Some(part) => vec![part],
+        None => vec![1, 2],
+    };
+    let answers = (puzzle.run)(&input, &parts, tracer.as_ref())
+        .map_err(|err| format!("day {}: {}", puzzle.day, err))?;
+    for (part, answer) in parts.into_iter().zip(answers) {
+        let answer = answer.map_err(|err| format!("day {} part {}: {}", puzzle.day, part, err))?;
+        match answer {
+            Some(answer) => println!("Day {} part {}: {}", puzzle.day, part, answer),
+            None if args.part.is_some() => {
+                return Err(format!("day {} has no part {}", puzzle.day, part));
+            }
+            None => {}
+        }
+    }
+
+    if let Some(tracer) = tracer {
+        let mut tracer = tracer.lock().unwrap();
+        tracer.finish().map_err(|err| err.to_string())?;
+        eprint!("{}", tracer);
+    }
+    Ok(())
+}
 
 fn main() {
-    day25::start();
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {}", message);
+            }
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(message) = run(args) {
+        eprintln!("error: {}", message);
+        std::process::exit(1);
+    }
 }
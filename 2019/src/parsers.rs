@@ -1,5 +1,31 @@
+use crate::error::{Error, Result};
+
 pub type ParseResult<'a, R> = Option<(&'a [u8], R)>;
 
+/// Check that a parser matched and consumed everything except trailing
+/// whitespace. `expected` describes the input for the error message.
+pub fn complete<R>(result: ParseResult<'_, R>, expected: &str) -> Result<R> {
+    match result {
+        Some((rest, result)) if rest.iter().all(u8::is_ascii_whitespace) => Ok(result),
+        Some((rest, _)) => Err(unexpected(&String::from_utf8_lossy(rest), expected)),
+        None => Err(Error::parse(format!("expected {}", expected))),
+    }
+}
+
+/// `complete` for parsers written with nom.
+pub fn complete_nom<R>(result: nom::IResult<&str, R>, expected: &str) -> Result<R> {
+    match result {
+        Ok((rest, result)) if rest.trim().is_empty() => Ok(result),
+        Ok((rest, _)) => Err(unexpected(rest, expected)),
+        Err(_) => Err(Error::parse(format!("expected {}", expected))),
+    }
+}
+
+fn unexpected(rest: &str, expected: &str) -> Error {
+    let found: String = rest.chars().take(20).collect();
+    Error::parse(format!("expected {}, found {:?}", expected, found))
+}
+
 pub fn token<P, R>(predicate: P) -> impl Fn(&[u8]) -> ParseResult<R>
 where
     P: Fn(u8) -> Option<R>,
@@ -13,7 +39,7 @@ where
 
 const EMPTY_SLICE: &[u8] = &[];
 #[allow(clippy::needless_lifetimes)]
-pub fn chunk<'a>(chunk: &'a [u8]) -> impl Fn(&'a [u8]) -> ParseResult<()> {
+pub fn chunk<'a>(chunk: &'a [u8]) -> impl Fn(&'a [u8]) -> ParseResult<'a, ()> {
     move |s| {
         if chunk == s.get(0..chunk.len())? {
             let rest = s.get(chunk.len()..).unwrap_or(EMPTY_SLICE);
@@ -57,7 +83,7 @@ where
     P: Fn(u8) -> bool,
 {
     move |s| {
-        s.get(0).map(|c| predicate(*c))?;
+        s.first().map(|c| predicate(*c))?;
 
         let mut ix = 1;
         for c in s.get(1..)? {
@@ -0,0 +1,214 @@
+use std::collections::VecDeque;
+
+use intcode::Machine;
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EdgeId(usize);
+
+#[derive(Debug, Clone)]
+struct Node {
+    machine: Machine,
+    /// read before anything on the incoming edges.
+    initial: VecDeque<i64>,
+    inputs: Vec<EdgeId>,
+    outputs: Vec<EdgeId>,
+}
+
+#[derive(Debug, Clone)]
+struct Edge {
+    /// `None` for an edge that only collects output.
+    to: Option<NodeId>,
+    queue: VecDeque<i64>,
+    /// everything sent along the edge.
+    sent: Vec<i64>,
+}
+
+/// Why a pipeline stopped running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Halted,
+    /// these nodes are waiting for input nothing will send.
+    Starved(Vec<NodeId>),
+}
+
+/// Machines joined by edges that queue up what one outputs for another to
+/// read. Every output goes along each of a node's outgoing edges, and a node
+/// reads its initial inputs and then its incoming edges in the order they
+/// were connected. Chains, rings and any other shape can be built.
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, machine: Machine) -> NodeId {
+        self.nodes.push(Node {
+            machine,
+            initial: VecDeque::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// queue values for `node` to read first.
+    pub fn input<I: IntoIterator<Item = i64>>(&mut self, node: NodeId, values: I) {
+        self.nodes[node.0].initial.extend(values);
+    }
+
+    fn add_edge(&mut self, from: NodeId, to: Option<NodeId>) -> EdgeId {
+        let edge = EdgeId(self.edges.len());
+        self.edges.push(Edge {
+            to,
+            queue: VecDeque::new(),
+            sent: Vec::new(),
+        });
+        self.nodes[from.0].outputs.push(edge);
+        if let Some(to) = to {
+            self.nodes[to.0].inputs.push(edge);
+        }
+        edge
+    }
+    pub fn connect(&mut self, from: NodeId, to: NodeId) -> EdgeId {
+        self.add_edge(from, Some(to))
+    }
+    /// an edge out of `from` that goes nowhere, to read its output from.
+    pub fn tap(&mut self, from: NodeId) -> EdgeId {
+        self.add_edge(from, None)
+    }
+
+    /// `machines` one after another, returning the nodes in order.
+    pub fn chain<I: IntoIterator<Item = Machine>>(&mut self, machines: I) -> Vec<NodeId> {
+        let nodes: Vec<_> = machines
+            .into_iter()
+            .map(|machine| self.add_node(machine))
+            .collect();
+        for pair in nodes.windows(2) {
+            self.connect(pair[0], pair[1]);
+        }
+        nodes
+    }
+
+    pub fn machine(&self, node: NodeId) -> &Machine {
+        &self.nodes[node.0].machine
+    }
+    /// everything sent along `edge`, whether it's been read or not.
+    pub fn sent(&self, edge: EdgeId) -> &[i64] {
+        &self.edges[edge.0].sent
+    }
+    /// the last value sent along `edge`.
+    pub fn last_sent(&self, edge: EdgeId) -> Result<i64> {
+        self.sent(edge)
+            .last()
+            .copied()
+            .ok_or_else(|| Error::no_solution("nothing was sent along the edge"))
+    }
+
+    /// Run one node on whatever is waiting for it, returning whether it
+    /// read or wrote anything.
+    fn run_node(&mut self, index: usize) -> Result<bool> {
+        let Node {
+            machine,
+            initial,
+            inputs,
+            outputs,
+        } = &mut self.nodes[index];
+        let edges = &mut self.edges;
+        let mut read = 0;
+        let input = std::iter::from_fn(|| {
+            let value = initial.pop_front().or_else(|| {
+                inputs
+                    .iter()
+                    .find_map(|edge| edges[edge.0].queue.pop_front())
+            });
+            read += value.is_some() as usize;
+            value
+        });
+        let output = machine.run(input)?;
+
+        for edge in outputs.iter() {
+            let edge = &mut self.edges[edge.0];
+            if edge.to.is_some() {
+                edge.queue.extend(output.iter().copied());
+            }
+            edge.sent.extend(output.iter().copied());
+        }
+        Ok(read > 0 || !output.is_empty())
+    }
+
+    /// Run every node in turn until each one has halted or is waiting for
+    /// input that nothing will send.
+    pub fn run(&mut self) -> Result<Status> {
+        loop {
+            let mut progress = false;
+            for index in 0..self.nodes.len() {
+                if !self.nodes[index].machine.is_halted() {
+                    progress |= self.run_node(index)?;
+                }
+            }
+            if !progress {
+                break;
+            }
+        }
+        let starved: Vec<_> = (0..self.nodes.len())
+            .filter(|&index| !self.nodes[index].machine.is_halted())
+            .map(NodeId)
+            .collect();
+        if starved.is_empty() {
+            Ok(Status::Halted)
+        } else {
+            Ok(Status::Starved(starved))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_programs::{adder, incrementer};
+
+    #[test]
+    fn chain() {
+        let mut pipeline = Pipeline::new();
+        let nodes = pipeline.chain(vec![incrementer(), incrementer(), incrementer()]);
+        let out = pipeline.tap(nodes[2]);
+        pipeline.input(nodes[0], vec![1, 10]);
+        assert_eq!(pipeline.run().unwrap(), Status::Starved(nodes.clone()));
+        assert_eq!(pipeline.sent(out), &[4, 13]);
+
+        pipeline.input(nodes[0], Some(0));
+        pipeline.input(nodes[1], Some(0));
+        pipeline.input(nodes[2], Some(0));
+        assert_eq!(pipeline.run().unwrap(), Status::Halted);
+    }
+
+    #[test]
+    fn diamond() {
+        // one value split two ways and added back together.
+        let mut pipeline = Pipeline::new();
+        let source = pipeline.add_node(incrementer());
+        let left = pipeline.add_node(incrementer());
+        let right = pipeline.add_node(incrementer());
+        let sink = pipeline.add_node(adder());
+        pipeline.connect(source, left);
+        pipeline.connect(source, right);
+        pipeline.connect(left, sink);
+        pipeline.connect(right, sink);
+        let out = pipeline.tap(sink);
+        pipeline.input(source, Some(1));
+        pipeline.run().unwrap();
+        pipeline.input(source, Some(2));
+        pipeline.run().unwrap();
+        assert_eq!(pipeline.sent(out), &[6, 8]);
+        assert_eq!(pipeline.last_sent(out).unwrap(), 8);
+    }
+}
@@ -0,0 +1,59 @@
+use intcode::tracer::SharedTracer;
+
+use crate::error::Result;
+use crate::solution::{run, Answers};
+use crate::*;
+
+pub const YEAR: u32 = 2019;
+
+/// A single day's puzzle, runnable without knowing its input type.
+pub struct Puzzle {
+    pub day: u32,
+    pub run: fn(&str, &[u32], Option<&SharedTracer>) -> Result<Answers>,
+}
+impl Puzzle {
+    pub fn default_input(&self) -> String {
+        format!("./inputs/day{:02}.txt", self.day)
+    }
+}
+
+macro_rules! puzzle {
+    ($day:expr, $solution:ty) => {
+        Puzzle {
+            day: $day,
+            run: run::<$solution>,
+        }
+    };
+}
+
+pub const PUZZLES: &[Puzzle] = &[
+    puzzle!(1, day01::Day01),
+    puzzle!(2, day02::Day02),
+    puzzle!(3, day03::Day03),
+    puzzle!(4, day04::Day04),
+    puzzle!(5, day05::Day05),
+    puzzle!(6, day06::Day06),
+    puzzle!(7, day07::Day07),
+    puzzle!(8, day08::Day08),
+    puzzle!(9, day09::Day09),
+    puzzle!(10, day10::Day10),
+    puzzle!(11, day11::Day11),
+    puzzle!(12, day12::Day12),
+    puzzle!(13, day13::Day13),
+    puzzle!(14, day14::Day14),
+    puzzle!(15, day15::Day15),
+    puzzle!(16, day16::Day16),
+    puzzle!(17, day17::Day17),
+    puzzle!(18, day18::Day18),
+    puzzle!(19, day19::Day19),
+    puzzle!(20, day20::Day20),
+    puzzle!(21, day21::Day21),
+    puzzle!(22, day22::Day22),
+    puzzle!(23, day23::Day23),
+    puzzle!(24, day24::Day24),
+    puzzle!(25, day25::Day25),
+];
+
+pub fn find(day: u32) -> Option<&'static Puzzle> {
+    PUZZLES.iter().find(|puzzle| puzzle.day == day)
+}
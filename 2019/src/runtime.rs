@@ -0,0 +1,463 @@
+use std::convert::TryFrom;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use intcode::{Machine, StopReason};
+
+use crate::error::{Error, Result};
+
+/// times in a row a non-blocking machine reads its empty value before it's
+/// counted as idle. From then on it waits for a value instead.
+pub(crate) const IDLE_POLLS: usize = 2;
+
+/// What a machine reads when there's nothing waiting for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    /// wait for a value.
+    Blocking,
+    /// read this value instead, `-1` on the day 23 network.
+    NonBlocking(i64),
+}
+
+/// Where a machine's outputs go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    /// every value to each of these nodes, nowhere if there are none.
+    Nodes(Vec<usize>),
+    /// `[address, x, y]` packets, `x` and `y` going to the node at
+    /// `address`. Packets for addresses outside the network come back as
+    /// `Event::Packet`.
+    Packets,
+}
+
+/// A machine and how it's connected.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub machine: Machine,
+    pub input: InputMode,
+    pub output: Output,
+    /// read before anything sent by other nodes.
+    pub initial: Vec<i64>,
+}
+impl Node {
+    pub fn new(machine: Machine, input: InputMode, output: Output) -> Self {
+        Node {
+            machine,
+            input,
+            output,
+            initial: Vec::new(),
+        }
+    }
+    pub fn with_initial<I: IntoIterator<Item = i64>>(mut self, values: I) -> Self {
+        self.initial.extend(values);
+        self
+    }
+}
+
+/// Something the running network wants the caller to know about.
+#[derive(Debug)]
+pub enum Event {
+    /// a packet for an address outside the network.
+    Packet([i64; 3]),
+    /// nothing is in flight and every node is waiting for input or halted.
+    Quiescent,
+    /// every node has halted.
+    Halted,
+}
+
+enum Message {
+    Value(i64),
+    Stop,
+}
+
+enum Notice {
+    Event(Event),
+    Fault(usize, Error),
+}
+
+/// What every node thread agrees on, behind one lock so that quiescence is
+/// never seen half way through a send. The senders are in here too, as
+/// they can't be shared between threads on their own before Rust 1.72.
+struct State {
+    senders: Vec<Sender<Message>>,
+    /// values sent to a node and not read yet.
+    in_flight: usize,
+    waiting: usize,
+    halted: Vec<bool>,
+}
+impl State {
+    fn halted_count(&self) -> usize {
+        self.halted.iter().filter(|&&halted| halted).count()
+    }
+    /// the event this state calls for, if any.
+    fn check(&self) -> Option<Event> {
+        let halted = self.halted_count();
+        if halted == self.halted.len() {
+            Some(Event::Halted)
+        } else if self.in_flight == 0 && self.waiting + halted == self.halted.len() {
+            Some(Event::Quiescent)
+        } else {
+            None
+        }
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    nodes: usize,
+}
+impl Shared {
+    /// Send `values` to `node` together, unless it has halted.
+    fn send(&self, node: usize, values: &[i64]) {
+        let mut state = self.state.lock().unwrap();
+        if state.halted[node] {
+            return;
+        }
+        for &value in values {
+            if state.senders[node].send(Message::Value(value)).is_ok() {
+                state.in_flight += 1;
+            }
+        }
+    }
+}
+
+/// The reading end of a node.
+struct Inbox {
+    index: usize,
+    mode: InputMode,
+    receiver: Receiver<Message>,
+    shared: Arc<Shared>,
+    notices: Sender<Notice>,
+    empty_reads: usize,
+}
+impl Inbox {
+    fn received(&mut self, message: Message) -> Option<i64> {
+        match message {
+            Message::Value(value) => {
+                self.shared.state.lock().unwrap().in_flight -= 1;
+                self.empty_reads = 0;
+                Some(value)
+            }
+            Message::Stop => None,
+        }
+    }
+
+    /// the next input, or `None` once the network is stopping.
+    fn read(&mut self) -> Option<i64> {
+        match self.receiver.try_recv() {
+            Ok(message) => return self.received(message),
+            Err(TryRecvError::Disconnected) => return None,
+            Err(TryRecvError::Empty) => {}
+        }
+        if let InputMode::NonBlocking(empty) = self.mode {
+            if self.empty_reads < IDLE_POLLS {
+                self.empty_reads += 1;
+                return Some(empty);
+            }
+        }
+
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            state.waiting += 1;
+            if let Some(event) = state.check() {
+                let _ = self.notices.send(Notice::Event(event));
+            }
+        }
+        let message = self.receiver.recv();
+        self.shared.state.lock().unwrap().waiting -= 1;
+        self.received(message.ok()?)
+    }
+
+    /// stop taking values, dropping the ones already sent.
+    fn halt(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.halted[self.index] = true;
+        while let Ok(message) = self.receiver.try_recv() {
+            if let Message::Value(_) = message {
+                state.in_flight -= 1;
+            }
+        }
+        if let Some(event) = state.check() {
+            let _ = self.notices.send(Notice::Event(event));
+        }
+    }
+}
+
+/// The writing end of a node.
+struct Outbox {
+    output: Output,
+    shared: Arc<Shared>,
+    notices: Sender<Notice>,
+    packet: Vec<i64>,
+}
+impl Outbox {
+    fn send(&mut self, value: i64) {
+        match &self.output {
+            Output::Nodes(nodes) => {
+                for &node in nodes.iter() {
+                    self.shared.send(node, &[value]);
+                }
+            }
+            Output::Packets => {
+                self.packet.push(value);
+                if let [address, x, y] = self.packet[..] {
+                    self.packet.clear();
+                    match usize::try_from(address) {
+                        Ok(node) if node < self.shared.nodes => {
+                            self.shared.send(node, &[x, y]);
+                        }
+                        _ => {
+                            let _ = self
+                                .notices
+                                .send(Notice::Event(Event::Packet([address, x, y])));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// a node's thread, returning its machine and everything it output.
+fn run_node(mut machine: Machine, mut inbox: Inbox, mut outbox: Outbox) -> (Machine, Vec<i64>) {
+    let mut outputs = Vec::new();
+    loop {
+        match machine.resume(std::iter::from_fn(|| inbox.read())) {
+            Ok(StopReason::Output(value)) => {
+                outputs.push(value);
+                inbox.empty_reads = 0;
+                outbox.send(value);
+            }
+            // only stopping leaves the machine without input.
+            Ok(StopReason::NeedsInput) => break,
+            Ok(StopReason::Halted) => {
+                inbox.halt();
+                break;
+            }
+            Err(err) => {
+                let _ = inbox.notices.send(Notice::Fault(inbox.index, err.into()));
+                inbox.halt();
+                break;
+            }
+        }
+    }
+    (machine, outputs)
+}
+
+/// A network of machines, each on its own thread.
+pub struct Runtime {
+    shared: Arc<Shared>,
+    notices: Receiver<Notice>,
+    threads: Vec<JoinHandle<(Machine, Vec<i64>)>>,
+}
+impl Runtime {
+    /// Start every node running.
+    pub fn start(nodes: Vec<Node>) -> Result<Self> {
+        let (senders, receivers): (Vec<_>, Vec<_>) =
+            nodes.iter().map(|_| mpsc::channel::<Message>()).unzip();
+        for (index, node) in nodes.iter().enumerate() {
+            if let Output::Nodes(targets) = &node.output {
+                if let Some(target) = targets.iter().find(|&&target| target >= nodes.len()) {
+                    return Err(Error::no_solution(format!(
+                        "node {} sends to node {}, there are only {}",
+                        index,
+                        target,
+                        nodes.len()
+                    )));
+                }
+            }
+        }
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                senders,
+                in_flight: 0,
+                waiting: 0,
+                halted: vec![false; nodes.len()],
+            }),
+            nodes: nodes.len(),
+        });
+        for (index, node) in nodes.iter().enumerate() {
+            shared.send(index, &node.initial);
+        }
+
+        let (notice_sender, notices) = mpsc::channel();
+        let threads = nodes
+            .into_iter()
+            .zip(receivers)
+            .enumerate()
+            .map(|(index, (node, receiver))| {
+                let inbox = Inbox {
+                    index,
+                    mode: node.input,
+                    receiver,
+                    shared: Arc::clone(&shared),
+                    notices: notice_sender.clone(),
+                    empty_reads: 0,
+                };
+                let outbox = Outbox {
+                    output: node.output,
+                    shared: Arc::clone(&shared),
+                    notices: notice_sender.clone(),
+                    packet: Vec::new(),
+                };
+                let machine = node.machine;
+                thread::spawn(move || run_node(machine, inbox, outbox))
+            })
+            .collect();
+        Ok(Runtime {
+            shared,
+            notices,
+            threads,
+        })
+    }
+
+    /// Wait for the next event. A machine faulting is an error.
+    pub fn next_event(&self) -> Result<Event> {
+        match self.notices.recv() {
+            Ok(Notice::Event(event)) => Ok(event),
+            Ok(Notice::Fault(index, err)) => Err(Error::no_solution(format!(
+                "node {} faulted: {}",
+                index, err
+            ))),
+            Err(_) => Err(Error::no_solution("every node has stopped")),
+        }
+    }
+
+    /// send values to a node from outside the network, none of them
+    /// interleaved with values from other nodes.
+    pub fn send(&self, node: usize, values: &[i64]) {
+        self.shared.send(node, values);
+    }
+
+    fn stop(&mut self) -> Vec<(Machine, Vec<i64>)> {
+        {
+            // still stop the other nodes if one panicked holding the lock.
+            let state = self.shared.state.lock();
+            let state = state.unwrap_or_else(|poisoned| poisoned.into_inner());
+            for sender in state.senders.iter() {
+                let _ = sender.send(Message::Stop);
+            }
+        }
+        self.threads
+            .drain(..)
+            .filter_map(|thread| thread.join().ok())
+            .collect()
+    }
+
+    /// Stop every node, returning the machines and everything each one
+    /// output, in node order.
+    pub fn finish(mut self) -> Result<Vec<(Machine, Vec<i64>)>> {
+        let count = self.threads.len();
+        let nodes = self.stop();
+        if nodes.len() != count {
+            return Err(Error::no_solution("a node thread panicked"));
+        }
+        Ok(nodes)
+    }
+}
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_programs::incrementer;
+    use intcode::assembler::assemble;
+
+    #[test]
+    fn chain_halts() {
+        let nodes = vec![
+            Node::new(incrementer(), InputMode::Blocking, Output::Nodes(vec![1]))
+                .with_initial(vec![1, 5, 0]),
+            Node::new(incrementer(), InputMode::Blocking, Output::Nodes(vec![])),
+        ];
+        let runtime = Runtime::start(nodes).unwrap();
+        // the second node never sees the zero, so it ends up waiting.
+        assert!(matches!(runtime.next_event().unwrap(), Event::Quiescent));
+        runtime.send(1, &[0]);
+        assert!(matches!(runtime.next_event().unwrap(), Event::Halted));
+        let nodes = runtime.finish().unwrap();
+        assert_eq!(nodes[0].1, vec![2, 6]);
+        assert_eq!(nodes[1].1, vec![3, 7]);
+        assert!(nodes.iter().all(|(machine, _)| machine.is_halted()));
+    }
+
+    #[test]
+    fn feedback_ring() {
+        // the day 7 part 2 examples: amplifiers in a loop, the last one
+        // feeding the first.
+        let examples = [
+            (
+                "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+                [9, 8, 7, 6, 5],
+                139629729,
+            ),
+            (
+                "3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,\
+                 -5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,\
+                 53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10",
+                [9, 7, 8, 5, 6],
+                18216,
+            ),
+        ];
+        for (program, phases, signal) in examples.iter() {
+            let machine = Machine::from_string(program).unwrap();
+            let nodes = phases
+                .iter()
+                .enumerate()
+                .map(|(index, &phase)| {
+                    let next = (index + 1) % phases.len();
+                    // the first signal goes in with the first phase, or the
+                    // ring could be seen waiting before it's sent.
+                    let initial = if index == 0 {
+                        vec![phase, 0]
+                    } else {
+                        vec![phase]
+                    };
+                    Node::new(
+                        machine.clone(),
+                        InputMode::Blocking,
+                        Output::Nodes(vec![next]),
+                    )
+                    .with_initial(initial)
+                })
+                .collect();
+            let runtime = Runtime::start(nodes).unwrap();
+            assert!(matches!(runtime.next_event().unwrap(), Event::Halted));
+            let nodes = runtime.finish().unwrap();
+            assert_eq!(nodes[phases.len() - 1].1.last(), Some(signal));
+        }
+    }
+
+    #[test]
+    fn packets() {
+        // sends one packet to 255 and waits, polling.
+        let program = "
+                  out #255
+                  out #7
+                  out #8
+            loop: in [value]
+                  jnz #1, #loop
+            value: data 0
+        ";
+        let machine = Machine::new(assemble(program).unwrap());
+        let nodes = vec![Node::new(
+            machine,
+            InputMode::NonBlocking(-1),
+            Output::Packets,
+        )];
+        let runtime = Runtime::start(nodes).unwrap();
+        assert!(matches!(
+            runtime.next_event().unwrap(),
+            Event::Packet([255, 7, 8])
+        ));
+        assert!(matches!(runtime.next_event().unwrap(), Event::Quiescent));
+        let nodes = runtime.finish().unwrap();
+        assert!(!nodes[0].0.is_halted());
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use intcode::Machine;
+
+use crate::error::{Error, Result};
+
+/// The version written into every save. Files without a version are the
+/// whole machine serialized by the old `Machine::save`, and are read as
+/// version 1.
+pub const FORMAT_VERSION: u32 = 2;
+
+pub const DEFAULT_DIRECTORY: &str = "./saves";
+
+/// Whatever the caller wants to remember about a save.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub day: Option<u32>,
+    pub score: Option<i64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
+}
+
+/// A save, without the machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveInfo {
+    pub slot: String,
+    pub version: u32,
+    /// seconds since the unix epoch, 0 for old saves.
+    pub saved_at: u64,
+    pub metadata: Metadata,
+}
+
+/// The saves in a directory.
+#[derive(Debug, Default)]
+pub struct SaveList {
+    /// oldest first.
+    pub saves: Vec<SaveInfo>,
+    /// slots that couldn't be read, and why.
+    pub unreadable: Vec<(String, Error)>,
+}
+
+/// Memory is stored as the runs of cells that differ from the program the
+/// machine was loaded from.
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    saved_at: u64,
+    metadata: Metadata,
+    program_len: usize,
+    program_hash: u64,
+    pc: usize,
+    sp: i64,
+    len: usize,
+    changes: Vec<(usize, Vec<i64>)>,
+}
+
+enum Loaded {
+    Current(SaveFile),
+    Legacy(Machine),
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is the same in every build.
+fn hash(program: &[i64]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for byte in program.iter().flat_map(|value| value.to_le_bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// the runs of cells where `memory` differs from `program`.
+fn diff(program: &Machine, memory: &Machine) -> Vec<(usize, Vec<i64>)> {
+    let len = program.mem_size().max(memory.mem_size());
+    let mut changes: Vec<(usize, Vec<i64>)> = Vec::new();
+    for addr in 0..len {
+        let value = memory.mem_get(addr);
+        if value == program.mem_get(addr) {
+            continue;
+        }
+        match changes.last_mut() {
+            Some((start, values)) if *start + values.len() == addr => values.push(value),
+            _ => changes.push((addr, vec![value])),
+        }
+    }
+    changes
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+/// Named save slots in a directory, one `<slot>.json` file each.
+#[derive(Debug, Clone)]
+pub struct SaveStore {
+    dir: PathBuf,
+}
+impl SaveStore {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        SaveStore { dir: dir.into() }
+    }
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, slot: &str) -> Result<PathBuf> {
+        let valid = !slot.is_empty()
+            && slot
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            return Err(Error::store(format!("invalid slot name {:?}", slot)));
+        }
+        Ok(self.dir.join(format!("{}.json", slot)))
+    }
+
+    pub fn exists(&self, slot: &str) -> bool {
+        matches!(self.path(slot), Ok(path) if path.is_file())
+    }
+
+    fn read(&self, slot: &str) -> Result<Loaded> {
+        let string = fs::read_to_string(self.path(slot)?)?;
+        let value: serde_json::Value = serde_json::from_str(&string)?;
+        match value.get("version").map(serde_json::Value::as_u64) {
+            None => Ok(Loaded::Legacy(serde_json::from_value(value)?)),
+            Some(Some(version)) if version == u64::from(FORMAT_VERSION) => {
+                Ok(Loaded::Current(serde_json::from_value(value)?))
+            }
+            Some(version) => Err(Error::store(format!(
+                "{}: unsupported format version {}",
+                slot,
+                version.map_or("?".to_string(), |version| version.to_string())
+            ))),
+        }
+    }
+
+    /// Save `machine`, which must have been loaded from `program`,
+    /// replacing whatever was in the slot.
+    pub fn save(
+        &self,
+        slot: &str,
+        program: &Machine,
+        machine: &Machine,
+        metadata: Metadata,
+    ) -> Result<()> {
+        let path = self.path(slot)?;
+        let original = program.memory();
+        let file = SaveFile {
+            version: FORMAT_VERSION,
+            saved_at: now(),
+            metadata,
+            program_len: original.len(),
+            program_hash: hash(&original),
+            pc: machine.pc(),
+            sp: machine.sp(),
+            len: machine.mem_size(),
+            changes: diff(program, machine),
+        };
+        fs::create_dir_all(&self.dir)?;
+        fs::write(path, serde_json::to_string(&file)?)?;
+        Ok(())
+    }
+
+    /// Load a save made from `program`.
+    pub fn load(&self, slot: &str, program: &Machine) -> Result<Machine> {
+        let file = match self.read(slot)? {
+            Loaded::Current(file) => file,
+            Loaded::Legacy(machine) => return Ok(machine),
+        };
+        let original = program.memory();
+        if file.program_len != original.len() || file.program_hash != hash(&original) {
+            return Err(Error::store(format!(
+                "{} was saved from a different program",
+                slot
+            )));
+        }
+
+        let mut machine = program.clone();
+        machine.set_registers(file.pc, file.sp);
+        for (start, values) in file.changes {
+            for (addr, value) in (start..).zip(values) {
+                machine.mem_set(addr, value);
+            }
+        }
+        if file.len > machine.mem_size() {
+            let last = file.len - 1;
+            machine.mem_set(last, machine.mem_get(last));
+        }
+        Ok(machine)
+    }
+
+    pub fn info(&self, slot: &str) -> Result<SaveInfo> {
+        let info = match self.read(slot)? {
+            Loaded::Current(file) => SaveInfo {
+                slot: slot.to_string(),
+                version: file.version,
+                saved_at: file.saved_at,
+                metadata: file.metadata,
+            },
+            Loaded::Legacy(_) => SaveInfo {
+                slot: slot.to_string(),
+                version: 1,
+                saved_at: 0,
+                metadata: Metadata::default(),
+            },
+        };
+        Ok(info)
+    }
+
+    /// every save in the directory, without giving up on unreadable ones.
+    pub fn list(&self) -> Result<SaveList> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(SaveList::default()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut list = SaveList::default();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            if let Some(slot) = path.file_stem().and_then(|stem| stem.to_str()) {
+                match self.info(slot) {
+                    Ok(info) => list.saves.push(info),
+                    Err(err) => list.unreadable.push((slot.to_string(), err)),
+                }
+            }
+        }
+        list.saves
+            .sort_by(|a, b| (a.saved_at, &a.slot).cmp(&(b.saved_at, &b.slot)));
+        list.unreadable.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(list)
+    }
+
+    pub fn delete(&self, slot: &str) -> Result<()> {
+        fs::remove_file(self.path(slot)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(name: &str) -> SaveStore {
+        let name = format!("advent-of-code-saves-{}-{}", name, std::process::id());
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        SaveStore::new(dir)
+    }
+
+    fn day13() -> Machine {
+        let buffer = fs::read_to_string("./inputs/day13.txt").unwrap();
+        Machine::from_string(&buffer).unwrap()
+    }
+
+    #[test]
+    fn delta_round_trip() {
+        let store = store("round-trip");
+        let program = day13();
+        let mut machine = program.clone();
+        machine.mem_set(0, 2);
+        machine.run(std::iter::once(0)).unwrap();
+
+        let metadata = Metadata {
+            day: Some(13),
+            score: Some(0),
+            ..Metadata::default()
+        };
+        store
+            .save("arcade", &program, &machine, metadata.clone())
+            .unwrap();
+        let restored = store.load("arcade", &program).unwrap();
+        assert_eq!(restored.pc(), machine.pc());
+        assert_eq!(restored.sp(), machine.sp());
+        assert_eq!(restored.memory(), machine.memory());
+
+        let SaveList { saves, unreadable } = store.list().unwrap();
+        assert!(unreadable.is_empty());
+        assert_eq!(saves.len(), 1);
+        assert_eq!(saves[0].slot, "arcade");
+        assert_eq!(saves[0].version, FORMAT_VERSION);
+        assert_eq!(saves[0].metadata, metadata);
+
+        // only the cells the game changed are stored.
+        let size = fs::metadata(store.dir().join("arcade.json")).unwrap().len();
+        assert!(size < 2000, "{} bytes", size);
+
+        store.delete("arcade").unwrap();
+        assert!(store.list().unwrap().saves.is_empty());
+        assert!(store.load("arcade", &program).is_err());
+    }
+
+    #[test]
+    fn rejected_saves() {
+        let store = store("rejected");
+        let program = day13();
+        store
+            .save("game", &program, &program, Metadata::default())
+            .unwrap();
+        let other = Machine::from_string("1,0,0,0,99").unwrap();
+        assert!(store.load("game", &other).is_err());
+        assert!(store
+            .save("../game", &program, &program, Metadata::default())
+            .is_err());
+
+        fs::write(store.dir().join("future.json"), r#"{"version": 99}"#).unwrap();
+        assert!(store.load("future", &program).is_err());
+
+        // one bad file doesn't hide the others.
+        fs::write(store.dir().join("corrupt.json"), "{").unwrap();
+        let SaveList { saves, unreadable } = store.list().unwrap();
+        assert_eq!(saves.len(), 1);
+        let slots: Vec<_> = unreadable.iter().map(|(slot, _)| slot.as_str()).collect();
+        assert_eq!(slots, vec!["corrupt", "future"]);
+    }
+
+    #[test]
+    fn legacy_saves() {
+        let store = SaveStore::new("./saves");
+        let machine = store.load("save_13", &day13()).unwrap();
+        assert_eq!(machine.pc(), 450);
+        assert_eq!(store.info("save_13").unwrap().version, 1);
+    }
+}
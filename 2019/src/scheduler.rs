@@ -0,0 +1,187 @@
+use std::collections::VecDeque;
+
+/// How a machine has used the network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacketStats {
+    pub sent: usize,
+    pub received: usize,
+    /// times it ran with nothing to receive.
+    pub polls: usize,
+}
+
+/// Picks which machine runs next.
+pub trait Policy {
+    /// `pending` is how many packets are waiting for each machine.
+    fn next(&mut self, pending: &[usize]) -> usize;
+    /// a packet was queued for `addr`.
+    fn queued(&mut self, _addr: usize) {}
+}
+
+/// Every machine in turn.
+#[derive(Debug, Clone, Default)]
+pub struct RoundRobin {
+    next: usize,
+}
+impl Policy for RoundRobin {
+    fn next(&mut self, pending: &[usize]) -> usize {
+        let index = self.next % pending.len();
+        self.next = index + 1;
+        index
+    }
+}
+
+/// The machines packets were sent to, in the order they were sent, and
+/// the others in turn once there are none.
+#[derive(Debug, Clone, Default)]
+pub struct EventDriven {
+    ready: VecDeque<usize>,
+    idle: RoundRobin,
+}
+impl Policy for EventDriven {
+    fn next(&mut self, pending: &[usize]) -> usize {
+        while let Some(index) = self.ready.pop_front() {
+            if pending[index] > 0 {
+                return index;
+            }
+        }
+        self.idle.next(pending)
+    }
+    fn queued(&mut self, addr: usize) {
+        if !self.ready.contains(&addr) {
+            self.ready.push_back(addr);
+        }
+    }
+}
+
+/// A random interleaving that is the same every time for the same seed.
+#[derive(Debug, Clone)]
+pub struct Interleaved {
+    state: u64,
+}
+impl Interleaved {
+    pub fn new(seed: u64) -> Self {
+        // xorshift never leaves zero.
+        Interleaved { state: seed | 1 }
+    }
+}
+impl Policy for Interleaved {
+    fn next(&mut self, pending: &[usize]) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state % pending.len() as u64) as usize
+    }
+}
+
+/// A policy, and what each machine has done so far. Unlike the threaded
+/// `Runtime`, a scheduled network runs one machine at a time, in an order
+/// that can be chosen and repeated.
+pub struct Scheduler {
+    policy: Box<dyn Policy>,
+    stats: Vec<PacketStats>,
+}
+impl Scheduler {
+    pub fn new<P: Policy + 'static>(policy: P) -> Self {
+        Scheduler {
+            policy: Box::new(policy),
+            stats: Vec::new(),
+        }
+    }
+    pub fn round_robin() -> Self {
+        Self::new(RoundRobin::default())
+    }
+    pub fn event_driven() -> Self {
+        Self::new(EventDriven::default())
+    }
+    pub fn interleaved(seed: u64) -> Self {
+        Self::new(Interleaved::new(seed))
+    }
+
+    /// Start counting for `machines` machines, forgetting any earlier run.
+    pub fn start(&mut self, machines: usize) {
+        self.stats = vec![PacketStats::default(); machines];
+    }
+    /// the machine to run next.
+    pub fn next(&mut self, pending: &[usize]) -> usize {
+        self.policy.next(pending)
+    }
+    /// a packet was queued for `addr`.
+    pub fn queued(&mut self, addr: usize) {
+        self.policy.queued(addr);
+    }
+    pub fn stats(&self) -> &[PacketStats] {
+        &self.stats
+    }
+    pub fn stats_mut(&mut self, index: usize) -> &mut PacketStats {
+        &mut self.stats[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_robin() {
+        let mut policy = RoundRobin::default();
+        let order: Vec<_> = (0..7).map(|_| policy.next(&[0, 3, 0])).collect();
+        assert_eq!(order, vec![0, 1, 2, 0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn event_driven() {
+        let mut policy = EventDriven::default();
+        policy.queued(2);
+        policy.queued(1);
+        policy.queued(2);
+        let pending = [0, 1, 1, 0];
+        assert_eq!(policy.next(&pending), 2);
+        assert_eq!(policy.next(&pending), 1);
+        // nothing left queued, so every machine in turn.
+        assert_eq!(policy.next(&pending), 0);
+        assert_eq!(policy.next(&pending), 1);
+
+        // a machine whose packets were already taken is skipped.
+        policy.queued(3);
+        policy.queued(1);
+        assert_eq!(policy.next(&[0, 1, 0, 0]), 1);
+    }
+
+    #[test]
+    fn interleaved() {
+        let order = |seed| {
+            let mut policy = Interleaved::new(seed);
+            (0..50).map(|_| policy.next(&[0; 5])).collect::<Vec<_>>()
+        };
+        assert_eq!(order(23), order(23));
+        assert_ne!(order(23), order(24));
+        assert!(order(0).iter().all(|&index| index < 5));
+        // every machine gets a turn.
+        assert!((0..5).all(|index| order(23).contains(&index)));
+    }
+
+    #[test]
+    fn stats() {
+        let mut scheduler = Scheduler::round_robin();
+        scheduler.start(2);
+        scheduler.stats_mut(1).sent += 2;
+        scheduler.stats_mut(0).polls += 1;
+        assert_eq!(
+            scheduler.stats(),
+            &[
+                PacketStats {
+                    sent: 0,
+                    received: 0,
+                    polls: 1
+                },
+                PacketStats {
+                    sent: 2,
+                    received: 0,
+                    polls: 0
+                },
+            ]
+        );
+        scheduler.start(3);
+        assert_eq!(scheduler.stats(), &[PacketStats::default(); 3]);
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt;
+
+use intcode::tracer::SharedTracer;
+
+use crate::error::Result;
+
+/// The answer to one part of a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Integer(i128),
+    String(String),
+    Image(Image),
+}
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Integer(int) => write!(f, "{}", int),
+            Answer::String(string) => write!(f, "{}", string),
+            Answer::Image(image) => write!(f, "\n{}", image),
+        }
+    }
+}
+
+macro_rules! answer_from_int {
+    ($($int:ty),*) => {
+        $(impl From<$int> for Answer {
+            fn from(int: $int) -> Self {
+                Answer::Integer(i128::from(int))
+            }
+        })*
+    };
+}
+answer_from_int!(i32, u32, i64, u64, i128);
+
+impl From<usize> for Answer {
+    fn from(int: usize) -> Self {
+        Answer::Integer(int as i128)
+    }
+}
+impl From<String> for Answer {
+    fn from(string: String) -> Self {
+        Answer::String(string)
+    }
+}
+impl From<&str> for Answer {
+    fn from(string: &str) -> Self {
+        Answer::String(string.to_string())
+    }
+}
+impl From<Image> for Answer {
+    fn from(image: Image) -> Self {
+        Answer::Image(image)
+    }
+}
+
+/// A black and white image, for puzzles whose answer has to be read off a
+/// screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    pixels: Vec<bool>,
+}
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Image {
+            width,
+            pixels: vec![false; width * height],
+        }
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.pixels.len() / self.width
+    }
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.pixels[y * self.width + x]
+    }
+    pub fn set(&mut self, x: usize, y: usize, lit: bool) {
+        self.pixels[y * self.width + x] = lit;
+    }
+}
+impl fmt::Display for Image {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.pixels.chunks(self.width).enumerate() {
+            if y != 0 {
+                writeln!(f)?;
+            }
+            for lit in row {
+                write!(f, "{}", if *lit { '#' } else { '.' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A day's puzzle. The input is parsed once and shared by both parts.
+pub trait Solution {
+    type Input<'a>;
+
+    /// Every day except the last has a second part.
+    const HAS_PART2: bool = true;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>>;
+    /// Trace the Intcode machines the puzzle runs. Puzzles without any
+    /// ignore the tracer.
+    fn trace(_input: &mut Self::Input<'_>, _tracer: &SharedTracer) {}
+    /// `parse` with the puzzle's machines traced from the start. Puzzles
+    /// that run a machine while parsing override this to trace it before it
+    /// runs, the rest are traced once parsed.
+    fn parse_traced<'a>(input: &'a str, tracer: &SharedTracer) -> Result<Self::Input<'a>> {
+        let mut parsed = Self::parse(input)?;
+        Self::trace(&mut parsed, tracer);
+        Ok(parsed)
+    }
+    fn part1(input: &Self::Input<'_>) -> Result<Answer>;
+    fn part2(input: &Self::Input<'_>) -> Result<Answer>;
+}
+
+/// The answer to each part asked for, or why it couldn't be found.
+pub type Answers = Vec<Result<Option<Answer>>>;
+
+/// Parse `input` once and run each of `parts` on it, in order. A part that
+/// doesn't exist gives `None`.
+pub fn run<S: Solution>(
+    input: &str,
+    parts: &[u32],
+    tracer: Option<&SharedTracer>,
+) -> Result<Answers> {
+    let parsed = match tracer {
+        Some(tracer) => S::parse_traced(input, tracer)?,
+        None => S::parse(input)?,
+    };
+    Ok(parts
+        .iter()
+        .map(|part| match part {
+            1 => S::part1(&parsed).map(Some),
+            2 if S::HAS_PART2 => S::part2(&parsed).map(Some),
+            _ => Ok(None),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_image() {
+        let mut image = Image::new(3, 2);
+        image.set(0, 0, true);
+        image.set(2, 1, true);
+        assert_eq!(image.to_string(), "#..\n..#");
+        assert_eq!(Answer::from(image).to_string(), "\n#..\n..#");
+    }
+}
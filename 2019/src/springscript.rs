@@ -0,0 +1,348 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use intcode::Machine;
+
+use crate::error::{Error, Result};
+
+/// the most instructions the springdroid's memory holds.
+pub const MAX_INSTRUCTIONS: usize = 15;
+/// how far past the end of a reported hull the droid can see.
+const SENSORS: usize = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    And,
+    Or,
+    Not,
+}
+
+/// The ground sensors `A` to `I`, one to nine tiles ahead, and the
+/// writable registers `T` and `J`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    Sensor(u8),
+    T,
+    J,
+}
+impl Register {
+    fn parse(name: &str) -> Option<Self> {
+        match name.as_bytes() {
+            [b'T'] => Some(Register::T),
+            [b'J'] => Some(Register::J),
+            &[c @ b'A'..=b'I'] => Some(Register::Sensor(c - b'A' + 1)),
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Register::Sensor(distance) => write!(f, "{}", char::from(b'A' + distance - 1)),
+            Register::T => write!(f, "T"),
+            Register::J => write!(f, "J"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub op: Op,
+    pub src: Register,
+    pub dst: Register,
+}
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.op {
+            Op::And => "AND",
+            Op::Or => "OR",
+            Op::Not => "NOT",
+        };
+        write!(f, "{} {} {}", op, self.src, self.dst)
+    }
+}
+
+/// `WALK` only has the sensors `A` to `D`, `RUN` has all nine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Walk,
+    Run,
+}
+impl Mode {
+    pub fn range(self) -> u8 {
+        match self {
+            Mode::Walk => 4,
+            Mode::Run => 9,
+        }
+    }
+}
+
+/// A springscript program, checked the way the springdroid checks it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    pub instructions: Vec<Instruction>,
+    pub mode: Mode,
+}
+impl Script {
+    pub fn new(instructions: Vec<Instruction>, mode: Mode) -> Result<Self> {
+        if instructions.len() > MAX_INSTRUCTIONS {
+            return Err(Error::parse(format!(
+                "{} instructions, the droid only holds {}",
+                instructions.len(),
+                MAX_INSTRUCTIONS
+            )));
+        }
+        for (number, instruction) in (1..).zip(instructions.iter()) {
+            if let Register::Sensor(distance) = instruction.dst {
+                return Err(error(number, format!("can't write to sensor {}", distance)));
+            }
+            match instruction.src {
+                Register::Sensor(distance) if distance > mode.range() => {
+                    return Err(error(
+                        number,
+                        format!("{} can't be read in {:?} mode", instruction.src, mode),
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(Script { instructions, mode })
+    }
+
+    /// Parse a script ending in `WALK` or `RUN`, ignoring blank lines.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut instructions = Vec::new();
+        let mut lines = (1..)
+            .zip(text.lines())
+            .filter(|(_, line)| !line.trim().is_empty());
+        for (number, line) in &mut lines {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let op = match words[0] {
+                "AND" => Op::And,
+                "OR" => Op::Or,
+                "NOT" => Op::Not,
+                "WALK" | "RUN" if words.len() == 1 => {
+                    let mode = if words[0] == "WALK" {
+                        Mode::Walk
+                    } else {
+                        Mode::Run
+                    };
+                    if let Some((number, _)) = lines.next() {
+                        return Err(error(number, format!("after {}", words[0])));
+                    }
+                    return Script::new(instructions, mode);
+                }
+                _ => return Err(error(number, format!("unknown instruction {:?}", line))),
+            };
+            let (src, dst) = match words[1..] {
+                [src, dst] => (Register::parse(src), Register::parse(dst)),
+                _ => return Err(error(number, format!("expected two registers: {:?}", line))),
+            };
+            match (src, dst) {
+                (Some(src), Some(dst)) => instructions.push(Instruction { op, src, dst }),
+                _ => return Err(error(number, format!("unknown register in {:?}", line))),
+            }
+        }
+        Err(Error::parse("no WALK or RUN at the end"))
+    }
+
+    /// whether the droid jumps with `ground` under each sensor, `A` first.
+    pub fn jumps(&self, ground: &[bool]) -> bool {
+        let (mut t, mut j) = (false, false);
+        for instruction in self.instructions.iter() {
+            let src = match instruction.src {
+                Register::Sensor(distance) => ground[usize::from(distance) - 1],
+                Register::T => t,
+                Register::J => j,
+            };
+            let dst = if instruction.dst == Register::T {
+                &mut t
+            } else {
+                &mut j
+            };
+            *dst = match instruction.op {
+                Op::And => *dst && src,
+                Op::Or => *dst || src,
+                Op::Not => !src,
+            };
+        }
+        j
+    }
+
+    /// Walk the droid over `hull`, which starts under the droid and is
+    /// ground past its end. Returns where the droid fell in if it did.
+    pub fn simulate(&self, hull: &[bool]) -> Option<usize> {
+        let ground = |x: usize| hull.get(x).copied().unwrap_or(true);
+        let mut x = 0;
+        while x < hull.len() {
+            let sensors: Vec<bool> = (1..=SENSORS).map(|distance| ground(x + distance)).collect();
+            x += if self.jumps(&sensors) { 4 } else { 1 };
+            if !ground(x) {
+                return Some(x);
+            }
+        }
+        None
+    }
+}
+impl fmt::Display for Script {
+    /// the script as the droid reads it, one instruction a line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instruction in self.instructions.iter() {
+            writeln!(f, "{}", instruction)?;
+        }
+        match self.mode {
+            Mode::Walk => writeln!(f, "WALK"),
+            Mode::Run => writeln!(f, "RUN"),
+        }
+    }
+}
+
+fn error(line: usize, message: String) -> Error {
+    Error::parse(format!("line {}: {}", line, message))
+}
+
+/// What the droid reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// the hull damage.
+    Damage(i64),
+    /// the hull the droid fell through, `true` for ground.
+    Fell(Vec<bool>),
+}
+
+/// Run `script` on the droid.
+pub fn submit(machine: &Machine, script: &Script) -> Result<Outcome> {
+    let output = machine
+        .clone()
+        .run(script.to_string().bytes().map(i64::from))?;
+    match output.last() {
+        Some(&damage) if u8::try_from(damage).is_err() => return Ok(Outcome::Damage(damage)),
+        _ => {}
+    }
+    let text: String = output
+        .iter()
+        .filter_map(|&c| u8::try_from(c).ok())
+        .map(char::from)
+        .collect();
+    // the first frame shows the hull before the droid is drawn on it.
+    text.lines()
+        .find(|line| line.contains('#') && line.chars().all(|c| c == '#' || c == '.'))
+        .map(|line| Outcome::Fell(line.chars().map(|c| c == '#').collect()))
+        .ok_or_else(|| Error::no_solution(format!("the droid said {:?}", text)))
+}
+
+/// The scripts `search_templates` tries, all filled in from one template:
+/// jump when `D` is ground and any of the chosen sensors before it is a
+/// hole, and when running only if the droid can move on from `D` to one of
+/// the chosen sensors past it. Any that the droid wouldn't accept are left
+/// out.
+fn templates(mode: Mode) -> impl Iterator<Item = Script> {
+    let later = (mode.range() - 4) as u32;
+    (1_u32..1 << 3).flat_map(move |holes| {
+        (0_u32..1 << later).filter_map(move |onwards| {
+            let sensor = |distance: u8| Register::Sensor(distance);
+            let instruction = |op, src, dst| Instruction { op, src, dst };
+            let mut instructions = Vec::new();
+            for distance in (1..=3).filter(|distance| holes & 1 << (distance - 1) != 0) {
+                if instructions.is_empty() {
+                    instructions.push(instruction(Op::Not, sensor(distance), Register::J));
+                } else {
+                    instructions.push(instruction(Op::Not, sensor(distance), Register::T));
+                    instructions.push(instruction(Op::Or, Register::T, Register::J));
+                }
+            }
+            instructions.push(instruction(Op::And, sensor(4), Register::J));
+            let onwards: Vec<u8> = (5..=mode.range())
+                .filter(|distance| onwards & 1 << (distance - 5) != 0)
+                .collect();
+            if let Some((&first, rest)) = onwards.split_first() {
+                instructions.push(instruction(Op::Not, sensor(first), Register::T));
+                instructions.push(instruction(Op::Not, Register::T, Register::T));
+                for &distance in rest {
+                    instructions.push(instruction(Op::Or, sensor(distance), Register::T));
+                }
+                instructions.push(instruction(Op::And, Register::T, Register::J));
+            }
+            Script::new(instructions, mode).ok()
+        })
+    })
+}
+
+/// A heuristic, not a search of every script: try each of the `templates`,
+/// shortest first, against the hulls the droid has fallen through so far,
+/// and submit the first that gets over them all. Returns the script and the
+/// hull damage it reports.
+///
+/// Hulls that need a script of any other shape aren't solved. Trying every
+/// script isn't practical instead: with 66 instructions to choose from when
+/// running there are millions of scripts that behave differently by five
+/// instructions long.
+pub fn search_templates(machine: &Machine, mode: Mode) -> Result<(Script, i64)> {
+    let mut scripts: Vec<Script> = templates(mode).collect();
+    scripts.sort_by_key(|script| script.instructions.len());
+    let mut hulls: Vec<Vec<bool>> = Vec::new();
+    for script in scripts {
+        if hulls.iter().any(|hull| script.simulate(hull).is_some()) {
+            continue;
+        }
+        match submit(machine, &script)? {
+            Outcome::Damage(damage) => return Ok((script, damage)),
+            Outcome::Fell(hull) => hulls.push(hull),
+        }
+    }
+    Err(Error::no_solution(format!(
+        "no script from the template gets over all {} hulls",
+        hulls.len()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hull(text: &str) -> Vec<bool> {
+        text.chars().map(|c| c == '#').collect()
+    }
+
+    #[test]
+    fn parse() {
+        let text = std::fs::read_to_string("./inputs/day21_2.txt").unwrap();
+        let script = Script::parse(&text).unwrap();
+        assert_eq!(script.mode, Mode::Run);
+        assert_eq!(script.instructions.len(), 12);
+        assert_eq!(script.to_string(), text.trim_end().to_string() + "\n");
+
+        assert!(Script::parse("NOT E J\nWALK").is_err());
+        assert!(Script::parse("NOT A B\nWALK").is_err());
+        assert!(Script::parse("NOT A J\nJUMP").is_err());
+        assert!(Script::parse("NOT A J").is_err());
+        assert!(Script::parse(&("NOT A J\n".repeat(16) + "WALK")).is_err());
+    }
+
+    #[test]
+    fn simulate() {
+        let script = Script::parse("NOT A J\nWALK").unwrap();
+        assert_eq!(script.simulate(&hull("#####.###########")), None);
+        assert_eq!(script.simulate(&hull("#####..#.########")), Some(8));
+
+        let script = Script::parse("NOT D J\nRUN").unwrap();
+        assert_eq!(script.simulate(&hull("#####.###########")), Some(5));
+    }
+
+    #[test]
+    fn search_and_submit() {
+        let buffer = std::fs::read_to_string("./inputs/day21.txt").unwrap();
+        let machine = Machine::from_string(&buffer).unwrap();
+
+        let by_hand = std::fs::read_to_string("./inputs/day21_1.txt").unwrap();
+        let walk = submit(&machine, &Script::parse(&by_hand).unwrap()).unwrap();
+        let (script, damage) = search_templates(&machine, Mode::Walk).unwrap();
+        assert!(script.instructions.len() <= MAX_INSTRUCTIONS);
+        assert_eq!(walk, Outcome::Damage(damage));
+
+        let by_hand = std::fs::read_to_string("./inputs/day21_2.txt").unwrap();
+        let run = submit(&machine, &Script::parse(&by_hand).unwrap()).unwrap();
+        let (_, damage) = search_templates(&machine, Mode::Run).unwrap();
+        assert_eq!(run, Outcome::Damage(damage));
+    }
+}
@@ -0,0 +1,33 @@
+//! Small Intcode programs shared by the tests of the modules that connect
+//! machines together.
+
+use intcode::assembler::assemble;
+use intcode::Machine;
+
+/// reads a value, outputs it plus one, until it reads zero.
+pub fn incrementer() -> Machine {
+    let program = "
+        loop: in [value]
+              jz [value], #end
+              add [value], #1, [value]
+              out [value]
+              jnz #1, #loop
+        end:  hlt
+        value: data 0
+    ";
+    Machine::new(assemble(program).unwrap())
+}
+
+/// adds pairs of values read.
+pub fn adder() -> Machine {
+    let program = "
+        loop: in [a]
+              in [b]
+              add [a], [b], [a]
+              out [a]
+              jnz #1, #loop
+        a:    data 0
+        b:    data 0
+    ";
+    Machine::new(assemble(program).unwrap())
+}